@@ -0,0 +1,428 @@
+use crate::crate_path::parse_crate_path;
+use crate::instructions::Separator;
+use proc_macro2::{Ident, TokenStream, TokenTree};
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::parse::ParseStream;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Attribute, LitStr, Meta, Path, Token, Type, WherePredicate};
+
+/// How a field that is not found in the parse string should be created.
+#[derive(Clone)]
+pub(crate) enum FieldDefault {
+    Trait,
+    Expr(TokenStream),
+}
+
+impl FieldDefault {
+    pub fn to_expr(&self) -> TokenStream {
+        match self {
+            FieldDefault::Trait => quote!(::core::default::Default::default()),
+            FieldDefault::Expr(e) => e.clone(),
+        }
+    }
+}
+
+/// A field of a struct or enum variant along with its `#[prse(...)]` attributes.
+#[derive(Clone)]
+pub(crate) struct Field {
+    /// The identifier of the field, `None` for tuple fields.
+    pub ident: Option<Ident>,
+    pub ty: Type,
+    pub with: Option<Path>,
+    pub default: Option<FieldDefault>,
+    pub skip: bool,
+    pub rename: Option<String>,
+    pub validate: Option<Path>,
+    /// A range expression that the field must be contained in.
+    pub range: Option<TokenStream>,
+    /// The function used to write the field when deriving `Display`.
+    pub display_with: Option<Path>,
+}
+
+impl Field {
+    pub fn new(field: &syn::Field) -> syn::Result<Self> {
+        let span = field
+            .ident
+            .as_ref()
+            .map_or_else(|| syn::spanned::Spanned::span(&field.ty), Ident::span);
+        let mut res = Field {
+            ident: field.ident.clone(),
+            ty: field.ty.clone(),
+            with: None,
+            default: None,
+            skip: false,
+            rename: None,
+            validate: None,
+            range: None,
+            display_with: None,
+        };
+
+        for a in field.attrs.iter().filter(|a| a.path().is_ident("prse")) {
+            if !matches!(a.meta, Meta::List(_)) {
+                return Err(syn::Error::new(
+                    a.bracket_token.span.join(),
+                    "Unexpected prse attribute.",
+                ));
+            }
+            a.parse_nested_meta(|meta| res.parse_meta(meta))?;
+        }
+
+        if res.skip
+            && (res.with.is_some()
+                || res.default.is_some()
+                || res.rename.is_some()
+                || res.validate.is_some()
+                || res.range.is_some()
+                || res.display_with.is_some())
+        {
+            return Err(syn::Error::new(
+                span,
+                "A skipped field cannot have any other prse attributes.",
+            ));
+        }
+        if res.rename.is_some() && res.ident.is_none() {
+            return Err(syn::Error::new(span, "Only named fields can be renamed."));
+        }
+        Ok(res)
+    }
+
+    fn parse_meta(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("with") {
+            set_once(&meta, &mut self.with, meta.value()?.parse()?)
+        } else if meta.path.is_ident("default") {
+            let default = if meta.input.peek(Token![=]) {
+                FieldDefault::Expr(parse_expr(meta.value()?)?)
+            } else {
+                FieldDefault::Trait
+            };
+            set_once(&meta, &mut self.default, default)
+        } else if meta.path.is_ident("skip") {
+            set_flag(&meta, &mut self.skip)
+        } else if meta.path.is_ident("rename") {
+            let name: LitStr = meta.value()?.parse()?;
+            if name.value().is_empty() {
+                return Err(syn::Error::new(name.span(), "Expected a non-empty name."));
+            }
+            set_once(&meta, &mut self.rename, name.value())
+        } else if meta.path.is_ident("validate") {
+            set_once(&meta, &mut self.validate, meta.value()?.parse()?)
+        } else if meta.path.is_ident("range") {
+            set_once(&meta, &mut self.range, parse_expr(meta.value()?)?)
+        } else if meta.path.is_ident("display_with") {
+            set_once(&meta, &mut self.display_with, meta.value()?.parse()?)
+        } else {
+            Err(meta.error(
+                "Unknown prse attribute, expected one of with, default, skip, rename, validate, range or display_with.",
+            ))
+        }
+    }
+
+    /// The name of this field in the parse string, `None` for tuple fields.
+    pub fn name(&self) -> Option<String> {
+        match (&self.rename, &self.ident) {
+            (Some(rename), _) => Some(rename.clone()),
+            (None, Some(ident)) => Some(ident.unraw().to_string()),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns whether this field can be used by the given variable name in the parse string.
+    pub fn has_name(&self, name: &Ident) -> bool {
+        match (&self.rename, &self.ident) {
+            (Some(rename), _) => name.unraw() == rename,
+            (None, Some(ident)) => name.unraw() == ident.unraw(),
+            (None, None) => false,
+        }
+    }
+}
+
+fn set_flag(meta: &ParseNestedMeta, value: &mut bool) -> syn::Result<()> {
+    if *value {
+        return Err(meta.error("Duplicated prse attribute."));
+    }
+    *value = true;
+    Ok(())
+}
+
+fn set_once<T>(meta: &ParseNestedMeta, value: &mut Option<T>, new: T) -> syn::Result<()> {
+    if value.is_some() {
+        return Err(meta.error("Duplicated prse attribute."));
+    }
+    *value = Some(new);
+    Ok(())
+}
+
+/// Parses the tokens of an expression up to the next top level comma.
+///
+/// This allows us to not require syn's full feature.
+fn parse_expr(input: ParseStream) -> syn::Result<TokenStream> {
+    let mut tokens = TokenStream::new();
+    while !input.is_empty() && !input.peek(Token![,]) {
+        tokens.extend(Some(input.parse::<TokenTree>()?));
+    }
+    if tokens.is_empty() {
+        return Err(input.error("Expected an expression."));
+    }
+    Ok(tokens)
+}
+
+/// The `#[prse(...)]` attributes of a struct or enum.
+#[derive(Default)]
+pub(crate) struct ContainerAttributes {
+    pub rename_all: Option<RenameRule>,
+    pub sep: Option<String>,
+    /// Return the variant whose pattern matches the most of the input.
+    pub longest_match: bool,
+    pub validate: Option<Path>,
+    /// Parse the struct as a list of key-value pairs.
+    pub kv: bool,
+    pub assign: Option<String>,
+    pub deny_unknown_fields: bool,
+    /// Parse each field in order, split by this separator.
+    pub tokens: Option<Separator>,
+    /// Also implement `FromStr` and `TryFrom<&str>`.
+    pub from_str: bool,
+    /// The where clause predicates that replace the inferred bounds.
+    pub bound: Option<Vec<WherePredicate>>,
+    /// The path to the prse crate.
+    pub crate_path: Option<Path>,
+    /// Also parse each variant from its discriminant.
+    pub discriminant: bool,
+    /// The pattern containing the tag that selects the variant and the rest of the input.
+    pub tag: Option<LitStr>,
+    /// Implement `ParseWith` for this context instead of `Parse`.
+    pub context: Option<Type>,
+}
+
+impl ContainerAttributes {
+    pub fn new(attrs: &[Attribute], is_enum: bool) -> syn::Result<Self> {
+        let mut res = ContainerAttributes::default();
+        let mut kv_option = None;
+        parse_list_attributes(attrs, |meta| {
+            if meta.path.is_ident("rename_all") && is_enum {
+                let rule: LitStr = meta.value()?.parse()?;
+                let rule = RenameRule::new(&rule.value())
+                    .ok_or_else(|| syn::Error::new(rule.span(), "Unknown rename rule, expected one of lowercase, UPPERCASE, PascalCase, camelCase, snake_case, SCREAMING_SNAKE_CASE, kebab-case or SCREAMING-KEBAB-CASE."))?;
+                set_once(&meta, &mut res.rename_all, rule)
+            } else if meta.path.is_ident("sep") {
+                let sep: LitStr = meta.value()?.parse()?;
+                if !is_enum && sep.value().is_empty() {
+                    return Err(syn::Error::new(
+                        sep.span(),
+                        "Expected a non-empty separator.",
+                    ));
+                }
+                kv_option.get_or_insert(meta.path.span());
+                set_once(&meta, &mut res.sep, sep.value())
+            } else if meta.path.is_ident("longest_match") && is_enum {
+                set_flag(&meta, &mut res.longest_match)
+            } else if meta.path.is_ident("discriminant") && is_enum {
+                set_flag(&meta, &mut res.discriminant)
+            } else if meta.path.is_ident("tag") && is_enum {
+                set_once(&meta, &mut res.tag, meta.value()?.parse()?)
+            } else if meta.path.is_ident("validate") && !is_enum {
+                set_once(&meta, &mut res.validate, meta.value()?.parse()?)
+            } else if meta.path.is_ident("kv") && !is_enum {
+                set_flag(&meta, &mut res.kv)
+            } else if meta.path.is_ident("assign") && !is_enum {
+                let assign: LitStr = meta.value()?.parse()?;
+                if assign.value().is_empty() {
+                    return Err(syn::Error::new(
+                        assign.span(),
+                        "Expected a non-empty separator.",
+                    ));
+                }
+                kv_option.get_or_insert(meta.path.span());
+                set_once(&meta, &mut res.assign, assign.value())
+            } else if meta.path.is_ident("tokens") && !is_enum {
+                let sep = if meta.input.peek(Token![=]) {
+                    let sep: LitStr = meta.value()?.parse()?;
+                    if sep.value().is_empty() {
+                        return Err(syn::Error::new(
+                            sep.span(),
+                            "Expected a non-empty separator.",
+                        ));
+                    }
+                    Separator::Str(sep.value())
+                } else {
+                    Separator::Whitespace
+                };
+                set_once(&meta, &mut res.tokens, sep)
+            } else if meta.path.is_ident("from_str") {
+                set_flag(&meta, &mut res.from_str)
+            } else if meta.path.is_ident("bound") {
+                let bound: LitStr = meta.value()?.parse()?;
+                let predicates =
+                    bound.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+                set_once(&meta, &mut res.bound, predicates.into_iter().collect())
+            } else if meta.path.is_ident("context") {
+                set_once(&meta, &mut res.context, meta.value()?.parse()?)
+            } else if meta.path.is_ident("crate") {
+                let path = parse_crate_path(&meta.value()?.parse()?)?;
+                set_once(&meta, &mut res.crate_path, path)
+            } else if meta.path.is_ident("deny_unknown_fields") && !is_enum {
+                kv_option.get_or_insert(meta.path.span());
+                set_flag(&meta, &mut res.deny_unknown_fields)
+            } else if ["rename_all", "longest_match", "discriminant", "tag"]
+                .iter()
+                .any(|a| meta.path.is_ident(a))
+            {
+                Err(meta.error("This prse attribute can only be used on enums."))
+            } else if meta.path.is_ident("validate") {
+                Err(meta.error("This prse attribute can only be used on structs and variants."))
+            } else if ["kv", "assign", "deny_unknown_fields", "tokens"]
+                .iter()
+                .any(|a| meta.path.is_ident(a))
+            {
+                Err(meta.error("This prse attribute can only be used on structs."))
+            } else {
+                Err(meta.error(
+                    "Unknown prse attribute, expected one of rename_all, sep, longest_match, discriminant, tag, validate, kv, assign, deny_unknown_fields, tokens, from_str, bound, context or crate.",
+                ))
+            }
+        })?;
+        match (kv_option, &res.context) {
+            (Some(span), _) if !is_enum && !res.kv => Err(syn::Error::new(
+                span,
+                "This prse attribute can only be used on enums or with the kv attribute.",
+            )),
+            (_, Some(context)) if res.from_str => Err(syn::Error::new_spanned(
+                context,
+                "The from_str attribute cannot be used with the context attribute.",
+            )),
+            _ => Ok(res),
+        }
+    }
+}
+
+/// The `#[prse(...)]` attributes of an enum variant.
+#[derive(Default)]
+pub(crate) struct VariantAttributes {
+    pub rename: Option<String>,
+    pub other: bool,
+    pub validate: Option<Path>,
+    /// The value of the tag that selects this variant.
+    pub tag_value: Option<String>,
+}
+
+impl VariantAttributes {
+    pub fn new(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut res = VariantAttributes::default();
+        parse_list_attributes(attrs, |meta| {
+            if meta.path.is_ident("rename") {
+                let name: LitStr = meta.value()?.parse()?;
+                set_once(&meta, &mut res.rename, name.value())
+            } else if meta.path.is_ident("other") {
+                set_flag(&meta, &mut res.other)
+            } else if meta.path.is_ident("validate") {
+                set_once(&meta, &mut res.validate, meta.value()?.parse()?)
+            } else if meta.path.is_ident("tag_value") {
+                let value: LitStr = meta.value()?.parse()?;
+                set_once(&meta, &mut res.tag_value, value.value())
+            } else {
+                Err(meta.error(
+                    "Unknown prse attribute, expected one of rename, other, validate or tag_value.",
+                ))
+            }
+        })?;
+        Ok(res)
+    }
+}
+
+fn parse_list_attributes(
+    attrs: &[Attribute],
+    mut parser: impl FnMut(ParseNestedMeta) -> syn::Result<()>,
+) -> syn::Result<()> {
+    for a in attrs
+        .iter()
+        .filter(|a| a.path().is_ident("prse") && matches!(a.meta, Meta::List(_)))
+    {
+        a.parse_nested_meta(&mut parser)?;
+    }
+    Ok(())
+}
+
+/// The case conversion used to create a pattern from a variant's name.
+#[derive(Copy, Clone)]
+pub(crate) enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn new(rule: &str) -> Option<Self> {
+        Some(match rule {
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "PascalCase" => RenameRule::Pascal,
+            "camelCase" => RenameRule::Camel,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "SCREAMING-KEBAB-CASE" => RenameRule::ScreamingKebab,
+            _ => return None,
+        })
+    }
+
+    /// Converts a PascalCase variant name using this rule.
+    pub fn apply(self, name: &str) -> String {
+        let separated = |sep: char| {
+            let mut res = String::new();
+            for (i, c) in name.chars().enumerate() {
+                if i > 0 && c.is_uppercase() {
+                    res.push(sep);
+                }
+                res.extend(c.to_lowercase());
+            }
+            res
+        };
+        match self {
+            RenameRule::Lower => name.to_lowercase(),
+            RenameRule::Upper => name.to_uppercase(),
+            RenameRule::Pascal => name.to_string(),
+            RenameRule::Camel => {
+                let mut chars = name.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_lowercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+            RenameRule::Snake => separated('_'),
+            RenameRule::ScreamingSnake => separated('_').to_uppercase(),
+            RenameRule::Kebab => separated('-'),
+            RenameRule::ScreamingKebab => separated('-').to_uppercase(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RenameRule;
+
+    #[test]
+    fn test_rename_rules() {
+        #[rustfmt::skip]
+        let cases = [
+            ("lowercase", "northeast"),
+            ("UPPERCASE", "NORTHEAST"),
+            ("PascalCase", "NorthEast"),
+            ("camelCase", "northEast"),
+            ("snake_case", "north_east"),
+            ("SCREAMING_SNAKE_CASE", "NORTH_EAST"),
+            ("kebab-case", "north-east"),
+            ("SCREAMING-KEBAB-CASE", "NORTH-EAST"),
+        ];
+        for (rule, expected) in cases {
+            assert_eq!(RenameRule::new(rule).unwrap().apply("NorthEast"), expected);
+        }
+        assert!(RenameRule::new("Title Case").is_none());
+    }
+}
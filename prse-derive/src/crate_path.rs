@@ -0,0 +1,69 @@
+use proc_macro2::{Group, Ident, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::parse::ParseStream;
+use syn::{LitStr, Path, Token};
+
+/// Parses the path of a crate that re-exports prse, given as `crate = "path"`.
+pub(crate) fn parse_crate_path(lit: &LitStr) -> syn::Result<Path> {
+    lit.parse()
+        .map_err(|_| syn::Error::new(lit.span(), "Expected the path to the prse crate."))
+}
+
+/// Parses a trailing `, crate = "path"` argument of a macro invocation if there is one.
+pub(crate) fn parse_trailing_crate_path(stream: ParseStream) -> syn::Result<Option<Path>> {
+    if stream.is_empty() {
+        return Ok(None);
+    }
+    let _coma: Token![,] = stream.parse()?;
+    let _crate: Token![crate] = stream.parse()?;
+    let _eq: Token![=] = stream.parse()?;
+    parse_crate_path(&stream.parse()?).map(Some)
+}
+
+/// Replaces every path starting with `::prse` in the generated code by the given path.
+///
+/// The generated code always refers to prse using an absolute path,
+/// so this allows a crate that re-exports prse to be used instead.
+pub(crate) fn replace_crate_path(tokens: TokenStream, path: &Path) -> TokenStream {
+    let trees: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut result = TokenStream::new();
+    let mut idx = 0;
+    while idx < trees.len() {
+        match &trees[idx] {
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), replace_crate_path(g.stream(), path));
+                group.set_span(g.span());
+                result.extend([TokenTree::Group(group)]);
+            }
+            _ if is_prse_path(&trees, idx) => {
+                path.to_tokens(&mut result);
+                idx += 3;
+                continue;
+            }
+            t => result.extend([t.clone()]),
+        }
+        idx += 1;
+    }
+    result
+}
+
+/// Whether the tokens at `idx` are `::prse::` and not part of a longer path.
+fn is_prse_path(trees: &[TokenTree], idx: usize) -> bool {
+    let is_colon =
+        |idx: usize| matches!(trees.get(idx), Some(TokenTree::Punct(p)) if p.as_char() == ':');
+    // Keywords such as `impl` or `use` can come before a path, unlike any other identifier.
+    let after_segment = idx > 0
+        && match &trees[idx - 1] {
+            TokenTree::Ident(i) => {
+                ["crate", "self", "super", "Self"].contains(&&*i.to_string())
+                    || syn::parse2::<Ident>(i.to_token_stream()).is_ok()
+            }
+            _ => false,
+        };
+    !after_segment
+        && is_colon(idx)
+        && is_colon(idx + 1)
+        && matches!(trees.get(idx + 2), Some(TokenTree::Ident(i)) if i == "prse")
+        && is_colon(idx + 3)
+        && is_colon(idx + 4)
+}
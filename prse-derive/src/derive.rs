@@ -1,32 +1,142 @@
-use crate::instructions::{Instruction, Instructions};
+use crate::attributes::{ContainerAttributes, Field, VariantAttributes};
+use crate::instructions::{Instruction, Instructions, Separator};
+use crate::shadow::shadowed_patterns;
 use crate::var::Var;
 use proc_macro2::{Ident, Span};
 use std::collections::HashSet;
+use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
 use syn::{
-    Attribute, Data, DeriveInput, Expr, Generics, Lit, LitStr, Meta, MetaNameValue, Variant,
+    Attribute, Data, DeriveInput, Expr, Generics, Lit, LitStr, Meta, MetaNameValue, Path, Type,
+    Variant, WherePredicate,
 };
 
 #[derive(Clone)]
 pub(crate) enum Derive {
     NoAttributes(Generics, Ident),
-    Struct(Generics, Ident, Fields),
-    Enum(Generics, Ident, Vec<(Ident, Fields)>),
+    Struct(Generics, Ident, Vec<Fields>),
+    /// An enum with the fields of each variant, a warning for each shadowed pattern,
+    /// the variants that can be parsed from their discriminant and, with the `longest_match` attribute,
+    /// the number of patterns that are compared before the catch-all variant is tried.
+    Enum(
+        Generics,
+        Ident,
+        Vec<(Ident, Fields)>,
+        Vec<(Span, String)>,
+        Option<Vec<Ident>>,
+        Option<usize>,
+    ),
+    KeyValue(Generics, Ident, KeyValue),
+    Tokens(Generics, Ident, Tokens),
+    Tagged(Generics, Ident, Tagged),
+}
+
+/// A derived type along with the extra implementations that should be generated for it.
+pub(crate) struct ParseDerive {
+    pub derive: Derive,
+    /// Also implement `FromStr` and `TryFrom<&str>` using the `Parse` implementation.
+    pub from_str: bool,
+    /// The where clause predicates that replace the inferred bounds of the `Parse` implementation.
+    pub bound: Option<Vec<WherePredicate>>,
+    pub crate_path: Option<Path>,
+    /// Implement `ParseWith` for this context instead of `Parse`.
+    pub context: Option<Type>,
+}
+
+/// A struct parsed from each of its fields in declaration order.
+#[derive(Clone)]
+pub(crate) struct Tokens {
+    pub fields: Vec<Field>,
+    pub is_named: bool,
+    /// The separator between each field.
+    pub sep: Separator,
+    pub validate: Option<Path>,
+}
+
+/// An enum whose variant is selected by the value of a tag, before parsing the rest of the input.
+#[derive(Clone)]
+pub(crate) struct Tagged {
+    /// The pattern containing the tag and a `{}` capture for the rest of the input.
+    pub instructions: Instructions,
+    /// The name of the tag capture.
+    pub name: String,
+    /// Each variant with its tag value and the patterns used to parse the rest of the input.
+    pub variants: Vec<(Ident, String, Vec<Fields>)>,
+}
+
+/// A struct parsed from key-value pairs that can be in any order.
+#[derive(Clone)]
+pub(crate) struct KeyValue {
+    pub fields: Vec<Field>,
+    /// The separator between each pair.
+    pub sep: String,
+    /// The separator between a key and its value.
+    pub assign: String,
+    pub deny_unknown_fields: bool,
+    pub validate: Option<Path>,
 }
 
 #[derive(Clone)]
 pub(crate) enum Fields {
-    Named(Instructions),
-    Unnamed(Instructions),
+    /// The instructions, the fields and the function used to validate the created value.
+    Named(Instructions, Vec<Field>, Option<Path>),
+    Unnamed(Instructions, Vec<Field>, Option<Path>),
     Unit(String),
+    /// Matches any input, used by a unit catch-all variant.
+    Any,
+}
+
+impl Derive {
+    /// The patterns used to parse the derived type.
+    fn patterns(&self) -> Vec<&Instructions> {
+        let fields: Vec<&Fields> = match self {
+            Derive::Struct(_, _, f) => f.iter().collect(),
+            Derive::Enum(_, _, v, ..) => v.iter().map(|(_, f)| f).collect(),
+            Derive::Tagged(_, _, tagged) => tagged.variants.iter().flat_map(|(.., f)| f).collect(),
+            Derive::NoAttributes(..) | Derive::KeyValue(..) | Derive::Tokens(..) => vec![],
+        };
+        let tag = match self {
+            Derive::Tagged(_, _, tagged) => Some(&tagged.instructions),
+            _ => None,
+        };
+        fields
+            .into_iter()
+            .filter_map(|f| match f {
+                Fields::Named(instructions, ..) | Fields::Unnamed(instructions, ..) => {
+                    Some(instructions)
+                }
+                Fields::Unit(_) | Fields::Any => None,
+            })
+            .chain(tag)
+            .collect()
+    }
+}
+
+impl Tagged {
+    /// Whether a unit variant without a pattern is written as only its tag,
+    /// which is the case when the tag and the rest of the input are only separated by a literal, such as `{kind} {}`.
+    pub fn is_bare_unit(&self, fields: &[Fields]) -> bool {
+        let is_separated = matches!(
+            self.instructions.0.as_slice(),
+            [Instruction::Parse(Var::Implied), Instruction::Lit(_), Instruction::Parse(_)]
+                | [Instruction::Parse(_), Instruction::Lit(_), Instruction::Parse(Var::Implied)]
+        );
+        is_separated && matches!(fields, [Fields::Unit(s)] if s.is_empty())
+    }
 }
 
 fn validate_fields(
     fields: syn::Fields,
     instructions: Instructions,
     span: Span,
+    validate: &Option<Path>,
 ) -> syn::Result<Fields> {
     match fields {
+        syn::Fields::Unit if validate.is_some() => Err(syn::Error::new(
+            span,
+            "The validate attribute cannot be used on a unit struct or variant.",
+        )),
         syn::Fields::Unit => {
             let mut iter = instructions.0.into_iter();
             match iter.next() {
@@ -39,29 +149,74 @@ fn validate_fields(
             }
         }
         syn::Fields::Named(fields) => {
-            let fields: Vec<_> = fields
+            let fields = fields
                 .named
-                .into_iter()
-                .map(|f| (f.ident.unwrap(), f.ty))
-                .collect();
-            let mut seen_idents = HashSet::new();
+                .iter()
+                .map(Field::new)
+                .collect::<syn::Result<Vec<_>>>()?;
+            let mut seen_fields = HashSet::new();
+            let mut seen_paths = HashSet::new();
             for i in instructions.0.iter() {
                 match i.get_var() {
                     None => {}
+                    Some(Var::Field(ident, path)) => {
+                        let Some(idx) = fields.iter().position(|f| !f.skip && f.has_name(ident))
+                        else {
+                            return Err(syn::Error::new(
+                                span,
+                                format!("Unexpected variable: {ident}"),
+                            ));
+                        };
+                        let field = &fields[idx];
+                        if field.with.is_some() || field.validate.is_some() || field.range.is_some()
+                        {
+                            return Err(syn::Error::new(
+                                span,
+                                "Nested fields cannot be used with the with, validate or range attributes.",
+                            ));
+                        }
+                        if seen_fields.contains(&idx) {
+                            return Err(syn::Error::new(
+                                span,
+                                format!("Cannot parse both {ident} and its nested fields."),
+                            ));
+                        }
+                        let path_string =
+                            path.iter().fold(ident.unraw().to_string(), |mut s, p| {
+                                s.push('.');
+                                s.push_str(&p.unraw().to_string());
+                                s
+                            });
+                        if !seen_paths.insert((idx, path_string.clone())) {
+                            return Err(syn::Error::new(
+                                span,
+                                format!("Duplicated variable: {path_string}"),
+                            ));
+                        }
+                    }
                     Some(Var::Ident(ident)) => {
-                        if fields.iter().any(|(i, _)| i == ident) {
-                            if seen_idents.contains(ident) {
+                        match fields.iter().position(|f| !f.skip && f.has_name(ident)) {
+                            Some(idx) => {
+                                if !seen_fields.insert(idx) {
+                                    return Err(syn::Error::new(
+                                        span,
+                                        format!("Duplicated variable: {ident}"),
+                                    ));
+                                }
+                                if seen_paths.iter().any(|(i, _)| *i == idx) {
+                                    return Err(syn::Error::new(
+                                        span,
+                                        format!("Cannot parse both {ident} and its nested fields."),
+                                    ));
+                                }
+                                validate_with(&fields[idx], i, span)?;
+                            }
+                            None => {
                                 return Err(syn::Error::new(
                                     span,
-                                    format!("Duplicated variable: {ident}"),
+                                    format!("Unexpected variable: {ident}"),
                                 ));
                             }
-                            seen_idents.insert(ident);
-                        } else {
-                            return Err(syn::Error::new(
-                                span,
-                                format!("Unexpected variable: {ident}"),
-                            ));
                         }
                     }
                     _ => {
@@ -72,71 +227,265 @@ fn validate_fields(
                     }
                 }
             }
-            Ok(Fields::Named(instructions))
+            // Fields that are not captured by the pattern must be created from their default.
+            let missing = fields.iter().enumerate().find(|(idx, f)| {
+                !f.skip
+                    && f.default.is_none()
+                    && !seen_fields.contains(idx)
+                    && !seen_paths.iter().any(|(i, _)| i == idx)
+            });
+            if let Some((_, field)) = missing {
+                let ident = field.ident.as_ref().unwrap().unraw();
+                return Err(syn::Error::new(
+                    span,
+                    format!("The field {ident} is not in the pattern and has no default value."),
+                ));
+            }
+            Ok(Fields::Named(instructions, fields, validate.clone()))
         }
         syn::Fields::Unnamed(fields) => {
-            let max = fields.unnamed.iter().count() - 1;
+            let fields = fields
+                .unnamed
+                .iter()
+                .map(Field::new)
+                .collect::<syn::Result<Vec<_>>>()?;
+            let parsed_fields: Vec<_> = fields.iter().filter(|f| !f.skip).collect();
+            let max = parsed_fields.len().saturating_sub(1);
             let mut count = 0;
+            let mut captured = vec![false; parsed_fields.len()];
             for i in instructions.0.iter() {
-                match i.get_var() {
-                    Some(Var::Ident(ident)) => {
+                let pos = match i.get_var() {
+                    Some(Var::Ident(ident) | Var::Field(ident, _)) => {
                         return Err(syn::Error::new(
                             span,
                             format!("Unexpected named variable: {ident}."),
                         ));
                     }
                     Some(Var::Implied) => {
-                        if count > max {
+                        if count >= parsed_fields.len() {
                             return Err(syn::Error::new(
                                 span,
                                 format!("Tuple variable must be between 0 and {max}."),
                             ));
                         }
                         count += 1;
+                        count - 1
                     }
-                    Some(Var::Position(pos)) if (*pos as usize) > max => {
+                    Some(Var::Position(pos)) if (*pos as usize) >= parsed_fields.len() => {
                         return Err(syn::Error::new(
                             span,
                             format!("Positional variable must be between 0 and {max}."),
                         ));
                     }
-                    _ => {}
-                }
+                    Some(Var::Position(pos)) => *pos as usize,
+                    None => continue,
+                };
+                validate_with(parsed_fields[pos], i, span)?;
+                captured[pos] = true;
+            }
+            // Fields that are not captured by the pattern must be created from their default.
+            let missing = fields
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| !f.skip)
+                .zip(captured)
+                .find(|((_, f), captured)| !captured && f.default.is_none());
+            if let Some(((idx, _), _)) = missing {
+                return Err(syn::Error::new(
+                    span,
+                    format!("The field {idx} is not in the pattern and has no default value."),
+                ));
             }
-            Ok(Fields::Unnamed(instructions))
+            Ok(Fields::Unnamed(instructions, fields, validate.clone()))
         }
     }
 }
 
-impl Parse for Derive {
+fn validate_with(field: &Field, instruction: &Instruction, span: Span) -> syn::Result<()> {
+    match instruction {
+        Instruction::Parse(_) | Instruction::FixedParse(..) => Ok(()),
+        _ if field.with.is_some() => Err(syn::Error::new(
+            span,
+            "The with attribute can only be used with a single capture.",
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl Parse for ParseDerive {
     fn parse(stream: ParseStream) -> syn::Result<Self> {
         let input: DeriveInput = stream.parse()?;
+        let is_enum = matches!(input.data, Data::Enum(_));
+        let container = ContainerAttributes::new(&input.attrs, is_enum)?;
+        if let (true, Some(lifetime)) = (container.from_str, input.generics.lifetimes().next()) {
+            return Err(syn::Error::new(
+                lifetime.lifetime.span(),
+                "The from_str attribute cannot be used on types with lifetimes.",
+            ));
+        }
+        let derive = Derive::new(input, &container)?;
+        if let (Derive::NoAttributes(..), Some(context)) = (&derive, &container.context) {
+            return Err(syn::Error::new_spanned(
+                context,
+                "The context attribute can only be used on types with a prse attribute.",
+            ));
+        }
+        if let Some(context) = &container.context {
+            for instructions in derive.patterns() {
+                instructions.validate_context(context.span())?;
+            }
+        }
+        Ok(ParseDerive {
+            derive,
+            from_str: container.from_str,
+            bound: container.bound,
+            crate_path: container.crate_path,
+            context: container.context,
+        })
+    }
+}
 
+impl Derive {
+    fn new(input: DeriveInput, container: &ContainerAttributes) -> syn::Result<Self> {
         match input.data {
             Data::Struct(s) => {
-                no_attributes(s.fields.iter().flat_map(|f| f.attrs.iter()))?;
-                match attribute_instruction(input.attrs.into_iter())? {
-                    None => Ok(Derive::NoAttributes(input.generics, input.ident)),
-                    Some((instructions, span)) => Ok(Derive::Struct(
-                        input.generics,
-                        input.ident,
-                        validate_fields(s.fields, instructions, span)?,
-                    )),
+                let instructions = attribute_instructions(input.attrs.into_iter())?;
+                if let Some(sep) = &container.tokens {
+                    if let Some((_, span)) = instructions.first() {
+                        return Err(syn::Error::new(
+                            *span,
+                            "A struct with the tokens attribute cannot have a prse pattern.",
+                        ));
+                    }
+                    if container.kv {
+                        return Err(syn::Error::new(
+                            input.ident.span(),
+                            "The tokens and kv attributes cannot be used together.",
+                        ));
+                    }
+                    if let syn::Fields::Unit = s.fields {
+                        return Err(syn::Error::new(
+                            input.ident.span(),
+                            "The tokens attribute cannot be used on a unit struct.",
+                        ));
+                    }
+                    let tokens = Tokens {
+                        fields: s
+                            .fields
+                            .iter()
+                            .map(Field::new)
+                            .collect::<syn::Result<_>>()?,
+                        is_named: matches!(s.fields, syn::Fields::Named(_)),
+                        sep: sep.clone(),
+                        validate: container.validate.clone(),
+                    };
+                    Ok(Derive::Tokens(input.generics, input.ident, tokens))
+                } else if container.kv {
+                    let kv = key_value(s.fields, container, &instructions, &input.ident)?;
+                    Ok(Derive::KeyValue(input.generics, input.ident, kv))
+                } else if instructions.is_empty() {
+                    if container.validate.is_some() {
+                        return Err(syn::Error::new(
+                            input.ident.span(),
+                            "The validate attribute can only be used with a prse attribute.",
+                        ));
+                    }
+                    if container.from_str {
+                        return Err(syn::Error::new(
+                            input.ident.span(),
+                            "The from_str attribute can only be used with a prse attribute.",
+                        ));
+                    }
+                    no_attributes(s.fields.iter().flat_map(|f| f.attrs.iter()))?;
+                    Ok(Derive::NoAttributes(input.generics, input.ident))
+                } else {
+                    let fields = instructions
+                        .into_iter()
+                        .map(|(instructions, span)| {
+                            validate_fields(
+                                s.fields.clone(),
+                                instructions,
+                                span,
+                                &container.validate,
+                            )
+                        })
+                        .collect::<syn::Result<_>>()?;
+                    Ok(Derive::Struct(input.generics, input.ident, fields))
                 }
             }
             Data::Enum(e) => {
-                no_attributes(input.attrs.iter())?;
-                no_attributes(
-                    e.variants
-                        .iter()
-                        .flat_map(|v| v.fields.iter().flat_map(|f| f.attrs.iter())),
-                )?;
-
-                let v_instructions = get_variant_attributes(e.variants.into_iter())?;
-                if v_instructions.is_empty() {
+                let mut pattern = attribute_instructions(input.attrs.iter().cloned())?;
+                if let Some((_, span)) = pattern.get(1) {
+                    return Err(syn::Error::new(
+                        *span,
+                        "Expected only a single prse attribute.",
+                    ));
+                }
+                let pattern = pattern.pop();
+                if let Some((instructions, span)) = &pattern {
+                    instructions.validate_enum_pattern(*span)?;
+                }
+
+                if let Some(tag) = &container.tag {
+                    if let Some((_, span)) = &pattern {
+                        return Err(syn::Error::new(
+                            *span,
+                            "An enum with the tag attribute cannot have a prse pattern.",
+                        ));
+                    }
+                    if container.discriminant || container.longest_match {
+                        return Err(syn::Error::new(
+                            tag.span(),
+                            "The tag attribute cannot be used with the discriminant or longest_match attributes.",
+                        ));
+                    }
+                    let tagged = tagged(e.variants.into_iter(), container, tag)?;
+                    return Ok(Derive::Tagged(input.generics, input.ident, tagged));
+                }
+
+                let discriminants = if container.discriminant {
+                    if let Some(v) = e.variants.iter().find(|v| !v.fields.is_empty()) {
+                        return Err(syn::Error::new(
+                            v.ident.span(),
+                            "The discriminant attribute can only be used on enums without fields.",
+                        ));
+                    }
+                    Some(e.variants.iter().map(|v| v.ident.clone()).collect())
+                } else {
+                    None
+                };
+
+                let variant_field_attrs: Vec<_> = e
+                    .variants
+                    .iter()
+                    .flat_map(|v| {
+                        v.attrs
+                            .iter()
+                            .chain(v.fields.iter().flat_map(|f| f.attrs.iter()))
+                    })
+                    .cloned()
+                    .collect();
+                let (v_instructions, compared) =
+                    get_variant_attributes(e.variants.into_iter(), container, &pattern)?;
+                let longest_match = container.longest_match.then_some(compared);
+                if v_instructions.is_empty() && discriminants.is_none() {
+                    no_attributes(input.attrs.iter())?;
+                    no_attributes(variant_field_attrs.iter())?;
                     Ok(Derive::NoAttributes(input.generics, input.ident))
                 } else {
-                    Ok(Derive::Enum(input.generics, input.ident, v_instructions))
+                    let warnings = shadowed_patterns(&v_instructions, longest_match);
+                    let variants = v_instructions
+                        .into_iter()
+                        .map(|(ident, fields, _)| (ident, fields))
+                        .collect();
+                    Ok(Derive::Enum(
+                        input.generics,
+                        input.ident,
+                        variants,
+                        warnings,
+                        discriminants,
+                        longest_match,
+                    ))
                 }
             }
             Data::Union(u) => Err(syn::Error::new(
@@ -147,25 +496,46 @@ impl Parse for Derive {
     }
 }
 
-fn attribute_instruction(
-    mut attrs: impl Iterator<Item = Attribute>,
-) -> syn::Result<Option<(Instructions, Span)>> {
-    if let Some(a) = attrs.next() {
-        if let Some(lit) = get_prse_lit(&a)? {
-            for a in attrs.by_ref() {
-                if get_prse_lit(&a)?.is_some() {
-                    return Err(syn::Error::new(
-                        a.bracket_token.span.join(),
-                        "Expected only a single prse attribute.",
-                    ));
-                }
-            }
-            let span = lit.span();
-            let lit_string = lit.value();
-            return Ok(Some((Instructions::new(&lit_string, span)?, span)));
+fn key_value(
+    fields: syn::Fields,
+    container: &ContainerAttributes,
+    instructions: &[(Instructions, Span)],
+    ident: &Ident,
+) -> syn::Result<KeyValue> {
+    if let Some((_, span)) = instructions.first() {
+        return Err(syn::Error::new(
+            *span,
+            "A struct with the kv attribute cannot have a prse pattern.",
+        ));
+    }
+    let syn::Fields::Named(fields) = fields else {
+        return Err(syn::Error::new(
+            ident.span(),
+            "The kv attribute can only be used on structs with named fields.",
+        ));
+    };
+    let fields: Vec<_> = fields
+        .named
+        .iter()
+        .map(Field::new)
+        .collect::<syn::Result<_>>()?;
+    let mut seen_keys = HashSet::new();
+    for field in fields.iter().filter(|f| !f.skip) {
+        let key = field.name().unwrap();
+        if !seen_keys.insert(key.clone()) {
+            return Err(syn::Error::new(
+                field.ident.as_ref().unwrap().span(),
+                format!("Duplicated key: {key}"),
+            ));
         }
     }
-    Ok(None)
+    Ok(KeyValue {
+        fields,
+        sep: container.sep.clone().unwrap_or_else(|| " ".into()),
+        assign: container.assign.clone().unwrap_or_else(|| "=".into()),
+        deny_unknown_fields: container.deny_unknown_fields,
+        validate: container.validate.clone(),
+    })
 }
 
 fn attribute_instructions(
@@ -192,6 +562,7 @@ fn get_prse_lit(a: &Attribute) -> syn::Result<Option<LitStr>> {
                     }),
                 ..
             }) => Ok(Some(l.clone())),
+            Meta::List(_) => Ok(None),
             _ => Err(syn::Error::new(
                 a.bracket_token.span.join(),
                 "prse attribute must be of the form #[prse = \"parse_string\"]",
@@ -205,17 +576,10 @@ fn get_prse_lit(a: &Attribute) -> syn::Result<Option<LitStr>> {
 fn no_attributes<'a>(attrs: impl Iterator<Item = &'a Attribute>) -> syn::Result<()> {
     #[allow(clippy::manual_try_fold)]
     attrs.fold(Ok(()), |i, a| {
+        if !a.path().is_ident("prse") {
+            return i;
+        }
         let error = syn::Error::new(a.bracket_token.span.join(), "Unexpected prse attribute.");
-        let error = match get_prse_lit(a).map(|a| a.is_some()) {
-            Err(mut e) => {
-                e.combine(error);
-                e
-            }
-            Ok(true) => error,
-            Ok(false) => {
-                return i;
-            }
-        };
         match i {
             Ok(()) => Err(error),
             Err(mut e) => {
@@ -226,24 +590,57 @@ fn no_attributes<'a>(attrs: impl Iterator<Item = &'a Attribute>) -> syn::Result<
     })
 }
 
+/// Each pattern of an enum along with the variant it creates and its span.
+type VariantPatterns = Vec<(Ident, Fields, Span)>;
+
+/// Returns the patterns of each variant, along with the number of them that come before
+/// the patterns of the catch-all variant.
 fn get_variant_attributes(
     iter: impl Iterator<Item = Variant>,
-) -> syn::Result<Vec<(Ident, Fields)>> {
+    container: &ContainerAttributes,
+    pattern: &Option<(Instructions, Span)>,
+) -> syn::Result<(VariantPatterns, usize)> {
     let attributes = iter
         .map(|v| {
+            let variant = VariantAttributes::new(&v.attrs)?;
+            let mut instructions = attribute_instructions(v.attrs.iter().cloned())?;
+            if let Some(rename) = &variant.rename {
+                if !instructions.is_empty() || variant.other {
+                    return Err(syn::Error::new(
+                        v.ident.span(),
+                        "A variant with a prse attribute cannot be renamed.",
+                    ));
+                }
+                instructions.push(variant_pattern(rename, &v, container)?);
+            } else if let Some(rule) = container.rename_all {
+                if instructions.is_empty() && !variant.other {
+                    let name = rule.apply(&v.ident.unraw().to_string());
+                    instructions.push(variant_pattern(&name, &v, container)?);
+                }
+            }
             Ok((
-                (v.ident, v.fields),
-                attribute_instructions(v.attrs.into_iter())?,
+                (v.ident, v.fields, variant.validate),
+                instructions,
+                variant.other,
             ))
         })
         .collect::<syn::Result<Vec<_>>>()?;
 
-    if attributes
-        .iter()
-        .any(|(_, instructions)| instructions.is_empty())
+    if let Some(((ident, ..), ..)) = attributes.iter().filter(|(.., other)| *other).nth(1) {
+        return Err(syn::Error::new(
+            ident.span(),
+            "Only a single variant can have the other attribute.",
+        ));
+    }
+
+    // Variants without a pattern can still be parsed from their discriminant.
+    if !container.discriminant
         && attributes
             .iter()
-            .any(|(_, instructions)| !instructions.is_empty())
+            .any(|(_, instructions, other)| instructions.is_empty() && !other)
+        && attributes
+            .iter()
+            .any(|(_, instructions, other)| !instructions.is_empty() || *other)
     {
         return Err(syn::Error::new(
             Span::call_site(),
@@ -251,15 +648,196 @@ fn get_variant_attributes(
         ));
     }
 
-    attributes
-        .into_iter()
-        .flat_map(|((v_ident, v_fields), instructions)| {
-            instructions.into_iter().map(move |(instr, span)| {
+    // The catch-all variant is always tried last.
+    let (others, attributes): (Vec<_>, Vec<_>) =
+        attributes.into_iter().partition(|(.., other)| *other);
+
+    let to_fields = |((v_ident, v_fields, validate), instructions, other): (
+        (Ident, syn::Fields, Option<Path>),
+        Vec<_>,
+        bool,
+    )| {
+        let catch_all = (other && instructions.is_empty()).then(|| {
+            let span = v_ident.span();
+            Ok((
+                v_ident.clone(),
+                catch_all_fields(&v_ident, v_fields.clone(), &validate)?,
+                span,
+            ))
+        });
+        instructions
+            .into_iter()
+            .map(move |(instr, span)| {
+                let instr = match pattern {
+                    Some((pattern, _)) => pattern.substitute(instr, span)?,
+                    None => instr,
+                };
                 Ok((
                     v_ident.clone(),
-                    validate_fields(v_fields.clone(), instr, span)?,
+                    validate_fields(v_fields.clone(), instr, span, &validate)?,
+                    span,
                 ))
             })
+            .chain(catch_all)
+    };
+
+    let mut variants = attributes
+        .into_iter()
+        .flat_map(to_fields)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let compared = variants.len();
+    for other in others.into_iter().flat_map(to_fields) {
+        variants.push(other?);
+    }
+    Ok((variants, compared))
+}
+
+/// Creates the fields of a catch-all variant that does not have a prse attribute,
+/// which parses the whole input into its only field.
+fn catch_all_fields(
+    ident: &Ident,
+    fields: syn::Fields,
+    validate: &Option<Path>,
+) -> syn::Result<Fields> {
+    if let syn::Fields::Unit = fields {
+        return match validate {
+            Some(_) => Err(syn::Error::new(
+                ident.span(),
+                "The validate attribute cannot be used on a unit struct or variant.",
+            )),
+            None => Ok(Fields::Any),
+        };
+    }
+    let parsed = fields
+        .iter()
+        .map(Field::new)
+        .filter(|f| !f.as_ref().is_ok_and(|f| f.skip))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let pattern =
+        match parsed.as_slice() {
+            [field] => format!("{{{}}}", field.name().unwrap_or_default()),
+            _ => return Err(syn::Error::new(
+                ident.span(),
+                "A variant with the other attribute must have a single field or a prse attribute.",
+            )),
+        };
+    let span = ident.span();
+    validate_fields(fields, Instructions::new(&pattern, span)?, span, validate)
+}
+
+/// Creates a pattern from the variant's name followed by each of its fields.
+fn variant_pattern(
+    name: &str,
+    variant: &Variant,
+    container: &ContainerAttributes,
+) -> syn::Result<(Instructions, Span)> {
+    let sep = escape(container.sep.as_deref().unwrap_or(" "));
+    let mut pattern = escape(name);
+    for capture in field_captures(variant)? {
+        pattern.push_str(&sep);
+        pattern.push_str(&capture);
+    }
+    let span = variant.ident.span();
+    Ok((Instructions::new(&pattern, span)?, span))
+}
+
+fn escape(s: &str) -> String {
+    s.replace('{', "{{").replace('}', "}}")
+}
+
+/// A capture for each field of the variant that is not skipped.
+fn field_captures(variant: &Variant) -> syn::Result<Vec<String>> {
+    let mut captures = vec![];
+    for field in variant.fields.iter() {
+        let field = Field::new(field)?;
+        if !field.skip {
+            let var = field.name().unwrap_or_default();
+            captures.push(format!("{{{var}}}"));
+        }
+    }
+    Ok(captures)
+}
+
+fn tagged(
+    variants: impl Iterator<Item = Variant>,
+    container: &ContainerAttributes,
+    tag: &LitStr,
+) -> syn::Result<Tagged> {
+    let span = tag.span();
+    let instructions = Instructions::new(&tag.value(), span)?;
+    let mut name = None;
+    let mut implied = 0;
+    for i in instructions.0.iter() {
+        match i {
+            Instruction::Lit(_) => {}
+            Instruction::Parse(Var::Implied) => implied += 1,
+            Instruction::Parse(Var::Ident(ident)) if name.is_none() => {
+                name = Some(ident.unraw().to_string())
+            }
+            _ => name = None,
+        }
+    }
+    let (Some(name), 1) = (name, implied) else {
+        return Err(syn::Error::new(
+            span,
+            "The tag attribute must contain a single named capture for the tag and a single {} for the rest of the input.",
+        ));
+    };
+
+    let mut seen_values = HashSet::new();
+    let variants = variants
+        .map(|v| {
+            let variant = VariantAttributes::new(&v.attrs)?;
+            if variant.other {
+                return Err(syn::Error::new(
+                    v.ident.span(),
+                    "The other attribute cannot be used on an enum with the tag attribute.",
+                ));
+            }
+            let value = match (variant.tag_value, variant.rename, container.rename_all) {
+                (Some(value), None, _) => value,
+                (None, Some(rename), _) => rename,
+                (None, None, Some(rule)) => rule.apply(&v.ident.unraw().to_string()),
+                (Some(_), Some(_), _) => {
+                    return Err(syn::Error::new(
+                        v.ident.span(),
+                        "A variant with a tag_value attribute cannot be renamed.",
+                    ))
+                }
+                (None, None, None) => return Err(syn::Error::new(
+                    v.ident.span(),
+                    "Each variant of an enum with the tag attribute needs a tag_value attribute.",
+                )),
+            };
+            if !seen_values.insert(value.clone()) {
+                return Err(syn::Error::new(
+                    v.ident.span(),
+                    format!("Duplicated tag value: {value}"),
+                ));
+            }
+
+            let mut patterns = attribute_instructions(v.attrs.iter().cloned())?;
+            // Without a pattern, the rest of the input contains each field separated by the separator.
+            if patterns.is_empty() && v.fields.is_empty() {
+                return Ok((v.ident, value, vec![Fields::Unit(String::new())]));
+            } else if patterns.is_empty() {
+                let sep = escape(container.sep.as_deref().unwrap_or(" "));
+                let pattern = field_captures(&v)?.join(&sep);
+                patterns.push((Instructions::new(&pattern, v.ident.span())?, v.ident.span()));
+            }
+            let fields = patterns
+                .into_iter()
+                .map(|(instructions, span)| {
+                    validate_fields(v.fields.clone(), instructions, span, &variant.validate)
+                })
+                .collect::<syn::Result<_>>()?;
+            Ok((v.ident, value, fields))
         })
-        .collect::<syn::Result<Vec<(Ident, Fields)>>>()
+        .collect::<syn::Result<_>>()?;
+
+    Ok(Tagged {
+        instructions,
+        name,
+        variants,
+    })
 }
@@ -0,0 +1,261 @@
+use crate::derive::Fields;
+use crate::instructions::Instruction;
+use proc_macro2::{Ident, TokenStream};
+use std::collections::BTreeMap;
+
+/// The number of patterns with a leading literal or a unit string needed before a dispatch table is generated.
+const MIN_PATTERNS: usize = 4;
+
+/// Selects the patterns of an enum that can match the input, so that only those are tried.
+///
+/// An input equal to the string of a unit variant is matched directly, as the patterns that can match it
+/// are known at compile time. Otherwise, as the text before a leading literal is ignored, the input is
+/// searched once for every leading literal using a trie.
+///
+/// The candidates are kept in a bitmask which is walked in declaration order, so that the first pattern
+/// that matches is still returned and the patterns that cannot match are never tried.
+pub(crate) struct Dispatch {
+    keys: Vec<Key>,
+}
+
+/// What the input must contain for a pattern to match.
+enum Key {
+    /// The pattern can match any input.
+    Any,
+    /// The input must contain the leading literal of the pattern.
+    Literal(String),
+    /// The input must be equal to the string of the unit variant.
+    Unit(String),
+}
+
+#[derive(Default)]
+struct Node {
+    /// The leading literals that end at this node.
+    ends: Vec<usize>,
+    children: BTreeMap<u8, Node>,
+}
+
+impl Dispatch {
+    pub fn new(variants: &[(Ident, Fields)]) -> Option<Self> {
+        let keys: Vec<_> = variants
+            .iter()
+            .map(|(_, f)| match f {
+                Fields::Named(instructions, ..) | Fields::Unnamed(instructions, ..) => {
+                    match instructions.0.first() {
+                        Some(Instruction::Lit(l)) => Key::Literal(l.clone()),
+                        _ => Key::Any,
+                    }
+                }
+                Fields::Unit(s) => Key::Unit(s.clone()),
+                Fields::Any => Key::Any,
+            })
+            .collect();
+
+        (keys.iter().filter(|k| !matches!(k, Key::Any)).count() >= MIN_PATTERNS)
+            .then_some(Dispatch { keys })
+    }
+
+    /// Generates the bitmask `__prse_candidates` of the patterns that can match the input.
+    pub fn gen_candidates(&self) -> TokenStream {
+        let mut units: Vec<&str> = vec![];
+        let mut literals: Vec<&str> = vec![];
+        for key in &self.keys {
+            match key {
+                Key::Unit(u) if !units.contains(&u.as_str()) => units.push(u),
+                Key::Literal(l) if !literals.contains(&l.as_str()) => literals.push(l),
+                _ => {}
+            }
+        }
+
+        let unit_arms = units.iter().map(|unit| {
+            let mask = self.gen_mask(|key| match key {
+                Key::Any => true,
+                Key::Literal(l) => unit.contains(l.as_str()),
+                Key::Unit(u) => u == unit,
+            });
+            quote!(#unit => #mask,)
+        });
+
+        let mut root = Node::default();
+        for (idx, literal) in literals.iter().enumerate() {
+            let node = literal
+                .bytes()
+                .fold(&mut root, |node, b| node.children.entry(b).or_default());
+            node.ends.push(idx);
+        }
+        let found = literals
+            .iter()
+            .map(|literal| {
+                let words = self.mask(|key| matches!(key, Key::Literal(l) if l == literal));
+                let updates = words
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, word)| **word != 0)
+                    .map(|(idx, word)| quote!(__prse_candidates[#idx] |= #word;));
+                quote!(#(#updates)*)
+            })
+            .collect::<Vec<_>>();
+        let trie = root.gen_match(0, &found);
+        let any = self.gen_mask(|key| matches!(key, Key::Any));
+        let words = (self.keys.len() + 63) / 64;
+
+        quote! {
+            let __prse_candidates: [u64; #words] = match s {
+                #(#unit_arms)*
+                _ => {
+                    let mut __prse_candidates = #any;
+                    let __prse_bytes = s.as_bytes();
+                    for __prse_start in 0..__prse_bytes.len() {
+                        let __prse_rest = &__prse_bytes[__prse_start..];
+                        #trie
+                    }
+                    __prse_candidates
+                }
+            };
+        }
+    }
+
+    /// Runs the attempt of each pattern in declaration order, running its `skipped` code instead
+    /// when the pattern is not in `__prse_candidates`, so that an error is still recorded for it.
+    ///
+    /// Only the patterns that can match any input have no `skipped` code, as they are always candidates.
+    pub fn gen_in_order(
+        &self,
+        attempts: Vec<TokenStream>,
+        skipped: Vec<Option<TokenStream>>,
+    ) -> TokenStream {
+        let steps =
+            attempts
+                .into_iter()
+                .zip(skipped)
+                .enumerate()
+                .map(|(idx, (attempt, skipped))| match skipped {
+                    Some(skipped) => {
+                        let word = idx / 64;
+                        let bit = 1_u64 << (idx % 64);
+                        quote! {
+                            if __prse_candidates[#word] & #bit != 0 {
+                                #attempt
+                            } else {
+                                #skipped
+                            }
+                        }
+                    }
+                    None => attempt,
+                });
+        quote!(#(#steps)*)
+    }
+
+    /// Runs the attempt of each pattern in the bitmask `mask`, in declaration order.
+    pub fn gen_dispatch(&self, mask: TokenStream, attempts: Vec<TokenStream>) -> TokenStream {
+        let indices = 0..attempts.len();
+        quote! {
+            let __prse_mask = #mask;
+            for (__prse_word, mut __prse_bits) in ::core::iter::IntoIterator::into_iter(__prse_mask).enumerate() {
+                while __prse_bits != 0 {
+                    let __prse_idx = __prse_word * 64 + __prse_bits.trailing_zeros() as usize;
+                    __prse_bits &= __prse_bits - 1;
+                    match __prse_idx {
+                        #(#indices => { #attempts })*
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn mask(&self, is_candidate: impl Fn(&Key) -> bool) -> Vec<u64> {
+        let mut words = vec![0; (self.keys.len() + 63) / 64];
+        for (idx, key) in self.keys.iter().enumerate() {
+            if is_candidate(key) {
+                words[idx / 64] |= 1 << (idx % 64);
+            }
+        }
+        words
+    }
+
+    fn gen_mask(&self, is_candidate: impl Fn(&Key) -> bool) -> TokenStream {
+        let words = self.mask(is_candidate);
+        quote!([#(#words),*])
+    }
+
+    /// Returns the patterns that are tried for the given input, in the order they are tried.
+    #[cfg(test)]
+    fn candidates(&self, input: &str) -> Vec<usize> {
+        let words = self.mask(|key| match key {
+            Key::Any => true,
+            Key::Literal(l) => input.contains(l.as_str()),
+            Key::Unit(u) => u == input,
+        });
+        (0..self.keys.len())
+            .filter(|idx| words[idx / 64] & (1 << (idx % 64)) != 0)
+            .collect()
+    }
+}
+
+impl Node {
+    fn gen_match(&self, depth: usize, found: &[TokenStream]) -> TokenStream {
+        let ends = self.ends.iter().map(|idx| &found[*idx]);
+        if self.children.is_empty() {
+            return quote!(#(#ends)*);
+        }
+        let arms = self.children.iter().map(|(b, child)| {
+            let inner = child.gen_match(depth + 1, found);
+            quote!(Some(#b) => { #inner })
+        });
+        quote! {
+            #(#ends)*
+            match __prse_rest.get(#depth).copied() {
+                #(#arms)*
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::{Ident, Span};
+
+    use super::Dispatch;
+    use crate::derive::Fields;
+    use crate::instructions::Instructions;
+
+    #[test]
+    fn test_dispatch_candidates() {
+        let pattern = |p: &str| {
+            Fields::Unnamed(
+                Instructions::new(p, Span::call_site()).unwrap(),
+                vec![],
+                None,
+            )
+        };
+        let variants: Vec<_> = [
+            pattern("move {}"),
+            pattern("move {} steps"),
+            pattern("{}"),
+            Fields::Unit("stop".into()),
+            Fields::Unit("stopped".into()),
+            pattern("say {}"),
+            pattern("s{}"),
+        ]
+        .into_iter()
+        .map(|f| (Ident::new("V", Span::call_site()), f))
+        .collect();
+        let dispatch = Dispatch::new(&variants).unwrap();
+
+        #[rustfmt::skip]
+        let cases: [(&str, &[usize]); 7] = [
+            ("move 3", &[0, 1, 2]),
+            ("move 3 steps", &[0, 1, 2, 6]),
+            ("stop", &[2, 3, 6]),
+            ("stopped", &[2, 4, 6]),
+            ("stops", &[2, 6]),
+            ("say hi", &[2, 5, 6]),
+            ("", &[2]),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(dispatch.candidates(input), expected, "{input}");
+        }
+    }
+}
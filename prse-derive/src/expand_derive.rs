@@ -1,68 +1,427 @@
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::ToTokens;
+use syn::ext::IdentExt;
 use syn::{
-    GenericParam, Generics, ImplGenerics, Lifetime, LifetimeParam, WhereClause, WherePredicate,
+    GenericParam, Generics, ImplGenerics, Lifetime, LifetimeParam, Path, PathArguments, Type,
+    WhereClause, WherePredicate,
 };
 
-use crate::derive::{Derive, Fields};
-use crate::instructions::Instructions;
+use crate::attributes::{Field, FieldDefault};
+use crate::crate_path::replace_crate_path;
+use crate::derive::{Derive, Fields, KeyValue, ParseDerive, Tagged, Tokens};
+use crate::dispatch::Dispatch;
+use crate::instructions::Separator;
+use crate::instructions::{Instruction, Instructions};
+use crate::var::Var;
 
-impl Derive {
+impl ParseDerive {
     pub fn into_token_stream(self) -> TokenStream {
+        let from_str = self.from_str.then(|| {
+            let (generics, name) = self.derive.header();
+            expand_from_str(generics, name)
+        });
+        let parse_with = self.context.is_none().then(|| {
+            let (generics, name) = self.derive.header();
+            expand_parse_with(generics, name)
+        });
+        let schema = self.derive.schema_tokens();
+        let derive = self.derive.into_token_stream(self.bound, self.context);
+        let tokens = quote! {
+            #derive
+            #parse_with
+            #from_str
+            #schema
+        };
+        match &self.crate_path {
+            Some(path) => replace_crate_path(tokens, path),
+            None => tokens,
+        }
+    }
+}
+
+impl Derive {
+    /// The generics and name of the derived type.
+    pub(crate) fn header(&self) -> (&Generics, &Ident) {
         match self {
-            Derive::NoAttributes(g, i) => expand_default(g, i),
-            Derive::Struct(mut g, name, f) => {
-                let (impl_generics, ty_generics, where_clause) = split_for_impl(&mut g, []);
-
-                let tokens = match f {
-                    Fields::Named(instructions) => expand_field(instructions, quote!(Self), None),
-                    Fields::Unnamed(instructions) => expand_tuple(instructions, quote!(Self), None),
-                    Fields::Unit(s) => expand_unit(s, quote!(Self), None),
+            Derive::NoAttributes(g, name)
+            | Derive::Struct(g, name, _)
+            | Derive::Enum(g, name, ..)
+            | Derive::KeyValue(g, name, _)
+            | Derive::Tokens(g, name, _)
+            | Derive::Tagged(g, name, _) => (g, name),
+        }
+    }
+
+    pub fn into_token_stream(
+        self,
+        bound: Option<Vec<WherePredicate>>,
+        context: Option<Type>,
+    ) -> TokenStream {
+        let with_context = context.is_some();
+        let (mut g, name, warnings, result) = match self {
+            Derive::NoAttributes(g, i) => return expand_default(g, i, &bound),
+            Derive::Struct(g, name, f) => {
+                let mut result = None;
+
+                // Each pattern is tried in order, falling back to the next one on failure.
+                for f in f.into_iter().rev() {
+                    result = Some(expand_fields(f, quote!(Self), result, with_context));
+                }
+                (g, name, vec![], quote!(#result))
+            }
+            Derive::KeyValue(g, name, kv) => (g, name, vec![], expand_key_value(kv, with_context)),
+            Derive::Tagged(g, name, tagged) => {
+                (g, name, vec![], expand_tagged(tagged, with_context))
+            }
+            Derive::Tokens(g, name, tokens) => {
+                (g, name, vec![], expand_tokens(tokens, with_context))
+            }
+            Derive::Enum(g, name, v, warnings, discriminants, longest_match) => {
+                let warnings = warnings
+                    .into_iter()
+                    .map(|(span, message)| expand_warning(span, message))
+                    .collect();
+                let discriminants =
+                    discriminants.map(|variants| expand_discriminants(&variants, v.is_empty()));
+
+                let dispatch = Dispatch::new(&v);
+                let candidates = dispatch.as_ref().map(Dispatch::gen_candidates);
+                // Without a leading literal every match uses the whole input, so the first one is the longest.
+                let longest_match = longest_match.filter(|&compared| {
+                    v[..compared]
+                        .iter()
+                        .any(|(_, f)| leading_literal(f).is_some())
+                });
+                let result = if v.is_empty() {
+                    None
+                } else if cfg!(feature = "alloc") {
+                    Some(expand_variants_alloc(
+                        v,
+                        &dispatch,
+                        longest_match,
+                        with_context,
+                    ))
+                } else {
+                    Some(expand_variants(v, &dispatch, longest_match, with_context))
                 };
+                let result = quote! {
+                    #discriminants
+                    #candidates
+                    #result
+                };
+                (g, name, warnings, result)
+            }
+        };
+        let (impl_generics, ty_generics, where_clause) =
+            split_for_impl(&mut g, [], &bound, context.as_ref());
 
-                quote! {
-                    #[automatically_derived]
-                    impl #impl_generics ::prse::Parse<'__prse_a> for #name #ty_generics #where_clause {
-                        fn from_str(s: &'__prse_a str) -> Result<Self, ::prse::ParseError> {
-                            #tokens
-                        }
+        // The original input is passed down so that spanned captures record their position within it.
+        if let Some(context) = context {
+            return quote! {
+                #(#warnings)*
+
+                #[automatically_derived]
+                impl #impl_generics ::prse::ParseWith<'__prse_a, #context> for #name #ty_generics #where_clause {
+                    fn from_str_with(
+                        s: &'__prse_a str,
+                        __prse_ctx: &mut #context,
+                    ) -> Result<Self, ::prse::ParseError> {
+                        <Self as ::prse::ParseWith<'__prse_a, #context>>::from_str_with_in(s, s, __prse_ctx)
+                    }
+
+                    #[allow(unused_variables)]
+                    fn from_str_with_in(
+                        s: &'__prse_a str,
+                        __prse_root: &'__prse_a str,
+                        __prse_ctx: &mut #context,
+                    ) -> Result<Self, ::prse::ParseError> {
+                        #result
                     }
                 }
+            };
+        }
+        quote! {
+            #(#warnings)*
+
+            #[automatically_derived]
+            impl #impl_generics ::prse::Parse<'__prse_a> for #name #ty_generics #where_clause {
+                fn from_str(s: &'__prse_a str) -> Result<Self, ::prse::ParseError> {
+                    <Self as ::prse::Parse<'__prse_a>>::from_str_in(s, s)
+                }
+
+                #[allow(unused_variables)]
+                fn from_str_in(
+                    s: &'__prse_a str,
+                    __prse_root: &'__prse_a str,
+                ) -> Result<Self, ::prse::ParseError> {
+                    #result
+                }
             }
-            Derive::Enum(mut g, name, v) => {
-                let (impl_generics, ty_generics, where_clause) = split_for_impl(&mut g, []);
+        }
+    }
+}
 
-                let mut result = None;
+/// Emits a warning at the given span by using a deprecated item,
+/// as proc macros cannot emit warnings on stable.
+fn expand_warning(span: Span, message: String) -> TokenStream {
+    quote_spanned! {span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            struct PrseShadowedPattern;
+            let _ = PrseShadowedPattern;
+        };
+    }
+}
 
-                for (variant, f) in v.into_iter().rev() {
-                    result = Some(match f {
-                        Fields::Named(instructions) => {
-                            expand_field(instructions, quote!(Self::#variant), result)
-                        }
-                        Fields::Unnamed(instructions) => {
-                            expand_tuple(instructions, quote!(Self::#variant), result)
+/// Parses the input as an integer and returns the variant with that discriminant.
+///
+/// If the variants also have patterns, they are tried when the input is not a known discriminant.
+fn expand_discriminants(variants: &[Ident], is_only: bool) -> TokenStream {
+    let checks = quote! {
+        #(if __prse_discriminant == Self::#variants as i128 {
+            return Ok(Self::#variants);
+        })*
+    };
+    if is_only {
+        quote! {
+            let __prse_discriminant = <i128 as ::prse::Parse<'_>>::from_str(s)?;
+            #checks
+            Err(::prse::__private::unknown_discriminant(__prse_discriminant))
+        }
+    } else {
+        quote! {
+            if let Ok(__prse_discriminant) = <i128 as ::prse::Parse<'_>>::from_str(s) {
+                #checks
+            }
+        }
+    }
+}
+
+/// The literal at the start of a pattern, as any input before it is ignored.
+fn leading_literal(f: &Fields) -> Option<&String> {
+    match f {
+        Fields::Named(instructions, ..) | Fields::Unnamed(instructions, ..) => {
+            match instructions.0.first() {
+                Some(Instruction::Lit(l)) => Some(l),
+                _ => None,
+            }
+        }
+        Fields::Unit(_) | Fields::Any => None,
+    }
+}
+
+/// Returns the variant `v` that was matched by the pattern at `idx`.
+///
+/// With the `longest_match` attribute, a match that ignored some input before its leading literal
+/// is kept in `__prse_best` instead, unless an earlier match ignored less of the input.
+/// It is returned once all the compared patterns have been tried.
+fn gen_matched(f: &Fields, idx: usize, longest_match: Option<usize>) -> TokenStream {
+    match (longest_match, leading_literal(f)) {
+        (Some(compared), Some(l)) if idx < compared => quote! {
+            {
+                let __prse_ignored = s.find(#l).unwrap_or(0);
+                if __prse_ignored == 0 {
+                    return Ok(v);
+                }
+                if __prse_best.as_ref().map_or(true, |(ignored, _)| __prse_ignored < *ignored) {
+                    __prse_best = Some((__prse_ignored, v));
+                }
+            }
+        },
+        _ => quote!(return Ok(v)),
+    }
+}
+
+/// Adds the handling of the best match to the attempts when the `longest_match` attribute is used,
+/// so that it is returned before the patterns of the catch-all variant are tried.
+fn gen_longest_match(
+    attempts: &mut [TokenStream],
+    longest_match: Option<usize>,
+) -> (Option<TokenStream>, Option<TokenStream>) {
+    let Some(compared) = longest_match else {
+        return (None, None);
+    };
+    let best = quote! {
+        if let Some((_, v)) = __prse_best.take() {
+            return Ok(v);
+        }
+    };
+    for attempt in &mut attempts[compared..] {
+        *attempt = quote!(#best #attempt);
+    }
+    (
+        Some(quote!(let mut __prse_best: ::core::option::Option<(usize, Self)> = None;)),
+        Some(best),
+    )
+}
+
+/// Tries each variant in order, returning the first one that matched
+/// or the errors of every variant if none of them matched.
+///
+/// With the `longest_match` attribute, the match that ignored the least input is returned instead.
+fn expand_variants_alloc(
+    v: Vec<(Ident, Fields)>,
+    dispatch: &Option<Dispatch>,
+    longest_match: Option<usize>,
+    context: bool,
+) -> TokenStream {
+    let alloc_crate = if cfg!(feature = "std") {
+        quote!(std)
+    } else {
+        quote!(alloc)
+    };
+    let unit_literals: Vec<_> = v
+        .iter()
+        .filter_map(|(_, f)| match f {
+            Fields::Unit(s) => Some(s),
+            _ => None,
+        })
+        .collect();
+    let suggestion = if unit_literals.is_empty() {
+        quote!(None)
+    } else {
+        quote!(::prse::__private::suggest(s, &[#(#unit_literals),*]))
+    };
+
+    let mut skipped = vec![];
+    let mut attempts: Vec<_> = v
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (variant, f))| {
+            let name = variant.unraw().to_string();
+            let to_return = quote!(Self::#variant);
+            // A variant that is skipped by the dispatch would have failed to find its leading literal.
+            let literal_error = match &f {
+                Fields::Unit(l) => Some(l),
+                f => leading_literal(f),
+            }
+            .map(|l| gen_literal_error(l.to_token_stream()));
+            skipped.push(
+                literal_error
+                    .as_ref()
+                    .map(|e| quote!(__prse_errors.push((#name, #e));)),
+            );
+            match f {
+                Fields::Unit(l) => quote! {
+                    if s == #l {
+                        return Ok(#to_return);
+                    }
+                    __prse_errors.push((#name, #literal_error));
+                },
+                f => {
+                    let matched = gen_matched(&f, idx, longest_match);
+                    let attempt = expand_fields(f, to_return, None, context);
+                    quote! {
+                        match #attempt {
+                            Ok(v) => #matched,
+                            Err(e) => __prse_errors.push((#name, e)),
                         }
-                        Fields::Unit(s) => expand_unit(s, quote!(Self::#variant), result),
-                    });
+                    }
                 }
+            }
+        })
+        .collect();
+    let (best, return_best) = gen_longest_match(&mut attempts, longest_match);
+    let attempts = match dispatch {
+        Some(dispatch) => dispatch.gen_in_order(attempts, skipped),
+        None => quote!(#(#attempts)*),
+    };
+
+    quote! {
+        let mut __prse_errors = ::#alloc_crate::vec::Vec::new();
+        #best
+        #attempts
+        #return_best
+        Err(::prse::ParseError::Variants {
+            errors: __prse_errors,
+            suggestion: #suggestion,
+        })
+    }
+}
 
-                quote! {
-                    #[automatically_derived]
-                    impl #impl_generics ::prse::Parse<'__prse_a> for #name #ty_generics #where_clause {
-                        fn from_str(s: &'__prse_a str) -> Result<Self, ::prse::ParseError> {
-                            #result
+/// Tries each variant in order, returning the error of the last variant if none of them matched.
+///
+/// With the `longest_match` attribute, the match that ignored the least input is returned instead.
+fn expand_variants(
+    v: Vec<(Ident, Fields)>,
+    dispatch: &Option<Dispatch>,
+    longest_match: Option<usize>,
+    context: bool,
+) -> TokenStream {
+    if dispatch.is_none() && longest_match.is_none() {
+        let mut result = None;
+        for (variant, f) in v.into_iter().rev() {
+            result = Some(expand_fields(f, quote!(Self::#variant), result, context));
+        }
+        return quote!(#result);
+    }
+
+    let literal_error = gen_literal_error(quote!(""));
+    let mut attempts: Vec<_> = v
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (variant, f))| {
+            let to_return = quote!(Self::#variant);
+            match f {
+                Fields::Unit(l) => quote! {
+                    if s == #l {
+                        return Ok(#to_return);
+                    }
+                    __prse_error = #literal_error;
+                },
+                f => {
+                    let matched = gen_matched(&f, idx, longest_match);
+                    let attempt = expand_fields(f, to_return, None, context);
+                    quote! {
+                        match #attempt {
+                            Ok(v) => #matched,
+                            Err(e) => __prse_error = e,
                         }
                     }
                 }
             }
+        })
+        .collect();
+    let (best, return_best) = gen_longest_match(&mut attempts, longest_match);
+    let attempts = match dispatch {
+        Some(dispatch) => dispatch.gen_dispatch(quote!(__prse_candidates), attempts),
+        None => quote!(#(#attempts)*),
+    };
+
+    quote! {
+        let mut __prse_error = #literal_error;
+        #best
+        #attempts
+        #return_best
+        Err(__prse_error)
+    }
+}
+
+fn expand_fields(
+    f: Fields,
+    to_return: TokenStream,
+    error: Option<TokenStream>,
+    context: bool,
+) -> TokenStream {
+    match f {
+        Fields::Named(instructions, fields, validate) => {
+            expand_field(instructions, &fields, validate, to_return, error, context)
+        }
+        Fields::Unnamed(instructions, fields, validate) => {
+            expand_tuple(instructions, &fields, validate, to_return, error, context)
         }
+        Fields::Unit(s) => expand_unit(s, to_return, error),
+        Fields::Any => quote!(Ok(#to_return)),
     }
 }
 
 fn expand_field(
     instructions: Instructions,
+    fields: &[Field],
+    validate: Option<Path>,
     to_return: TokenStream,
     error: Option<TokenStream>,
+    context: bool,
 ) -> TokenStream {
     let func_name = format_ident!("__prse_func");
     let mut renames = vec![];
@@ -77,11 +436,33 @@ fn expand_field(
         let mut __prse_remaining = __prse_input;
     };
 
-    instructions.gen_body(&mut body);
+    instructions.gen_body(&mut body, context);
 
-    let function = instructions.gen_function(body, func_name.clone());
+    let function = instructions.gen_function(body, func_name.clone(), context);
+
+    let mut conversions = vec![];
+    let mut field_values = vec![];
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let value = if field.skip {
+            quote!(::core::default::Default::default())
+        } else if let Some((_, r)) = renames.iter().find(|(l, _)| field.has_name(l)) {
+            conversions.extend(gen_conversion(field, r));
+            conversions.extend(gen_validation(field, r, ident.unraw().to_string()));
+            r.to_token_stream()
+        } else if let Some(nested) = gen_nested(&instructions, field) {
+            nested
+        } else if let Some(default) = &field.default {
+            default.to_expr()
+        } else {
+            continue;
+        };
+        field_values.push(quote!(#ident: #value));
+    }
 
-    let fields = renames.iter().map(|(l, r)| quote!(#l: #r));
+    let result = gen_result(&func_name, &func_idents, conversions, context);
+    let value = quote!(#to_return { #(#field_values),* });
+    let result = gen_match(result, &func_idents, value, validate, error);
 
     quote! {
         {
@@ -89,37 +470,68 @@ fn expand_field(
 
             #function
 
-            match #func_name (s) {
-                Ok(( #(#func_idents),* )) => {
-                    Ok(( #to_return { #(#fields),* }))
-                }
-                Err(e) => #error,
-            }
+            #result
         }
     }
 }
 
 fn expand_tuple(
     instructions: Instructions,
+    fields: &[Field],
+    validate: Option<Path>,
     to_return: TokenStream,
     error: Option<TokenStream>,
+    context: bool,
 ) -> TokenStream {
     let func_name = format_ident!("__prse_func");
     let mut _renames = vec![];
-    let mut return_idents = vec![];
+    let mut _return_idents = vec![];
     let mut func_idents = vec![];
     let error = error.unwrap_or_else(|| quote!(Err(e)));
 
-    instructions.gen_return_idents(&mut return_idents, &mut func_idents, &mut _renames);
+    instructions.gen_return_idents(&mut _return_idents, &mut func_idents, &mut _renames);
+
+    // Each capture is assigned to the field at its position, ignoring skipped fields.
+    let mut captures = vec![None; fields.len()];
+    let mut implied = 0;
+    for (idx, i) in instructions.0.iter().enumerate() {
+        match i.get_var() {
+            Some(Var::Implied) => {
+                captures[implied] = Some(format_ident!("__prse_{idx}"));
+                implied += 1;
+            }
+            Some(Var::Position(p)) => captures[*p as usize] = Some(format_ident!("__prse_pos_{p}")),
+            _ => {}
+        }
+    }
 
     let mut body = quote! {
         let mut __prse_parse: &str;
         let mut __prse_remaining = __prse_input;
     };
 
-    instructions.gen_body(&mut body);
+    instructions.gen_body(&mut body, context);
+
+    let function = instructions.gen_function(body, func_name.clone(), context);
 
-    let function = instructions.gen_function(body, func_name.clone());
+    let mut conversions = vec![];
+    let mut field_values = vec![];
+    let mut captures = captures.into_iter();
+    for (idx, field) in fields.iter().enumerate() {
+        if field.skip {
+            field_values.push(quote!(::core::default::Default::default()));
+        } else if let Some(r) = captures.next().flatten() {
+            conversions.extend(gen_conversion(field, &r));
+            conversions.extend(gen_validation(field, &r, idx.to_string()));
+            field_values.push(r.to_token_stream());
+        } else if let Some(default) = &field.default {
+            field_values.push(default.to_expr());
+        }
+    }
+
+    let result = gen_result(&func_name, &func_idents, conversions, context);
+    let value = quote!(#to_return ( #(#field_values),* ));
+    let result = gen_match(result, &func_idents, value, validate, error);
 
     quote! {
         {
@@ -127,36 +539,425 @@ fn expand_tuple(
 
             #function
 
-            match #func_name (s) {
+            #result
+        }
+    }
+}
+
+/// Matches the tag pattern once, then parses the rest of the input using the variant selected by the tag.
+fn expand_tagged(tagged: Tagged, context: bool) -> TokenStream {
+    let bare_units: Vec<_> = tagged
+        .variants
+        .iter()
+        .filter(|(_, _, fields)| tagged.is_bare_unit(fields))
+        .map(|(_, value, _)| value.clone())
+        .collect();
+    let Tagged {
+        instructions,
+        name,
+        variants,
+    } = tagged;
+    let func_name = format_ident!("__prse_func");
+    let mut body = quote! {
+        let mut __prse_parse: &str;
+        let mut __prse_remaining = __prse_input;
+    };
+    // The tag and the rest of the input are string slices, which do not need the context.
+    instructions.gen_body(&mut body, false);
+    let function = instructions.gen_function(body, func_name.clone(), false);
+    let tag_first = instructions
+        .0
+        .iter()
+        .find_map(Instruction::get_var)
+        .is_some_and(|v| !matches!(v, Var::Implied));
+    let captures = if tag_first {
+        quote!((__prse_tag, __prse_rest))
+    } else {
+        quote!((__prse_rest, __prse_tag))
+    };
+    let call = if bare_units.is_empty() {
+        quote!(#func_name(s, __prse_root)?)
+    } else {
+        let bare = if tag_first {
+            quote!((s, ""))
+        } else {
+            quote!(("", s))
+        };
+        quote! {
+            match #func_name(s, __prse_root) {
+                Ok(v) => v,
+                // A unit variant can be written without the separator.
+                Err(_) if matches!(s, #(#bare_units)|*) => #bare,
+                Err(e) => return Err(e),
+            }
+        }
+    };
+
+    let arms = variants.into_iter().map(|(variant, value, fields)| {
+        let mut result = None;
+        // Each pattern of the variant is tried in order, falling back to the next one on failure.
+        for f in fields.into_iter().rev() {
+            result = Some(expand_fields(f, quote!(Self::#variant), result, context));
+        }
+        quote! {
+            #value => {
+                let s = __prse_rest;
+                #result
+            }
+        }
+    });
+
+    quote! {
+        let #captures: (&str, &str) = {
+            use ::prse::Parse;
+
+            #function
+
+            #call
+        };
+        match __prse_tag {
+            #(#arms)*
+            _ => Err(::prse::__private::unknown_tag(#name, __prse_tag)),
+        }
+    }
+}
+
+/// Splits the input into key-value pairs, parsing each value into the field with the same key.
+fn expand_key_value(kv: KeyValue, context: bool) -> TokenStream {
+    let KeyValue {
+        fields,
+        sep,
+        assign,
+        deny_unknown_fields,
+        validate,
+    } = kv;
+    let value = format_ident!("__prse_value");
+    let mut slots = vec![];
+    let mut arms = vec![];
+    let mut field_values = vec![];
+
+    for (idx, field) in fields.iter().enumerate() {
+        let ident = field.ident.as_ref().unwrap();
+        if field.skip {
+            field_values.push(quote!(#ident: ::core::default::Default::default()));
+            continue;
+        }
+        let slot = format_ident!("__prse_{idx}");
+        let key = field.name().unwrap();
+        let parse = gen_conversion(field, &value).unwrap_or_else(|| {
+            let parse = gen_parse(&value, context);
+            quote!(let #value = #parse?;)
+        });
+        let validation = gen_validation(field, &value, ident.unraw().to_string());
+        arms.push(quote! {
+            #key => {
+                if #slot.is_some() {
+                    return Err(::prse::__private::duplicate_key(__prse_key));
+                }
+                #parse
+                #validation
+                #slot = Some(#value);
+            }
+        });
+
+        // Optional fields parse their inner type and are None when missing.
+        let missing = match &field.default {
+            Some(default) => default.to_expr(),
+            None if is_option(&field.ty) => quote!(None),
+            None => quote!(return Err(::prse::__private::missing_key(#key))),
+        };
+        let found = if is_option(&field.ty) {
+            quote!(Some(#value))
+        } else {
+            value.to_token_stream()
+        };
+        field_values.push(quote! {
+            #ident: match #slot {
+                Some(#value) => #found,
+                None => #missing,
+            }
+        });
+        slots.push(slot);
+    }
+
+    let unknown = if deny_unknown_fields {
+        quote!(return Err(::prse::__private::unknown_key(__prse_key)))
+    } else {
+        quote!({})
+    };
+    let literal_error = if cfg!(feature = "alloc") {
+        quote!(::prse::ParseError::Literal {expected: (#assign).into(), found: __prse_item.into()})
+    } else {
+        quote!(::prse::ParseError::Literal)
+    };
+    let validate =
+        validate.map(|validate| quote!(::prse::__private::validate(#validate(&#value), None)?;));
+
+    quote! {
+        #( let mut #slots = None; )*
+        let __prse_items = ::prse::ParseIter::<&str>::new(s, ::prse::Separator::Str(#sep))
+            .skip_empty()
+            .nested();
+        for __prse_item in __prse_items {
+            let __prse_item = __prse_item?;
+            let (__prse_key, #value) = __prse_item
+                .split_once(#assign)
+                .ok_or_else(|| #literal_error)?;
+            match __prse_key {
+                #(#arms)*
+                _ => #unknown,
+            }
+        }
+        let #value = Self { #(#field_values),* };
+        #validate
+        Ok(#value)
+    }
+}
+
+/// Splits the input by the separator, parsing each item into the next field.
+fn expand_tokens(tokens: Tokens, context: bool) -> TokenStream {
+    let Tokens {
+        fields,
+        is_named,
+        sep,
+        validate,
+    } = tokens;
+    let value = format_ident!("__prse_value");
+    let count = fields.iter().filter(|f| !f.skip).count() as u8;
+    let mut parsed = vec![];
+    let mut field_values = vec![];
+
+    for (idx, field) in fields.iter().enumerate() {
+        let field_value = if field.skip {
+            quote!(::core::default::Default::default())
+        } else {
+            let ident = format_ident!("__prse_{idx}");
+            let found = parsed.len() as u8;
+            let missing = match &field.default {
+                Some(default) => default.to_expr(),
+                None => quote! {
+                    return Err(::prse::ParseError::Array {
+                        expected: #count,
+                        found: #found,
+                    })
+                },
+            };
+            let parse = gen_conversion(field, &ident).unwrap_or_else(|| {
+                let parse = gen_parse(&ident, context);
+                quote!(let #ident = #parse?;)
+            });
+            let name = field
+                .ident
+                .as_ref()
+                .map_or_else(|| idx.to_string(), |i| i.unraw().to_string());
+            let validation = gen_validation(field, &ident, name);
+            parsed.push(quote! {
+                let #ident = match __prse_iter.next() {
+                    Some(#ident) => {
+                        let #ident = #ident?;
+                        #parse
+                        #validation
+                        #ident
+                    }
+                    None => #missing,
+                };
+            });
+            ident.to_token_stream()
+        };
+        field_values.push(match &field.ident {
+            Some(name) => quote!(#name: #field_value),
+            None => field_value,
+        });
+    }
+
+    // Runs of whitespace are treated as a single separator.
+    let skip_empty = matches!(sep, Separator::Whitespace).then(|| quote!(.skip_empty()));
+    let value_expr = if is_named {
+        quote!(Self { #(#field_values),* })
+    } else {
+        quote!(Self ( #(#field_values),* ))
+    };
+    let validate =
+        validate.map(|validate| quote!(::prse::__private::validate(#validate(&#value), None)?;));
+
+    quote! {
+        let mut __prse_iter = ::prse::ParseIter::<&str>::new(s, #sep) #skip_empty;
+        #(#parsed)*
+        let __prse_count_left = __prse_iter.count();
+        if __prse_count_left != 0 {
+            return Err(::prse::ParseError::Array {
+                expected: #count,
+                found: #count + __prse_count_left as u8,
+            });
+        }
+        let #value = #value_expr;
+        #validate
+        Ok(#value)
+    }
+}
+
+pub(crate) fn is_option(ty: &Type) -> bool {
+    let Type::Path(p) = ty else {
+        return false;
+    };
+    p.qself.is_none()
+        && p.path.segments.last().is_some_and(|s| {
+            s.ident == "Option" && matches!(s.arguments, PathArguments::AngleBracketed(_))
+        })
+}
+
+/// Creates a field from the captures of its nested fields,
+/// starting from the field's default value.
+fn gen_nested(instructions: &Instructions, field: &Field) -> Option<TokenStream> {
+    let assignments: Vec<_> = instructions
+        .0
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, i)| match i.get_var() {
+            Some(Var::Field(ident, path)) if field.has_name(ident) => {
+                let value = format_ident!("__prse_{idx}");
+                Some(quote!(__prse_nested #(.#path)* = #value;))
+            }
+            _ => None,
+        })
+        .collect();
+    if assignments.is_empty() {
+        return None;
+    }
+    let ty = &field.ty;
+    let default = field
+        .default
+        .as_ref()
+        .map_or_else(|| FieldDefault::Trait.to_expr(), FieldDefault::to_expr);
+    Some(quote! {
+        {
+            let mut __prse_nested: #ty = #default;
+            #(#assignments)*
+            __prse_nested
+        }
+    })
+}
+
+/// Parses the string slice `ident` which is a part of `s`, passing the context if there is one.
+fn gen_parse(ident: &Ident, context: bool) -> TokenStream {
+    if context {
+        quote!(::prse::__private::try_parse_context_with(#ident, s, __prse_root, __prse_ctx))
+    } else {
+        quote!(::prse::__private::try_parse_context(#ident, s, __prse_root))
+    }
+}
+
+/// Converts the captured string slice of a field using its `with` function.
+fn gen_conversion(field: &Field, ident: &Ident) -> Option<TokenStream> {
+    let with = field.with.as_ref()?;
+    Some(quote! {
+        let #ident = ::prse::__private::add_err_context(#with(#ident), s, #ident)?;
+    })
+}
+
+/// Checks that a parsed field is valid using its `validate` function and `range`.
+fn gen_validation(field: &Field, ident: &Ident, name: String) -> Option<TokenStream> {
+    if field.validate.is_none() && field.range.is_none() {
+        return None;
+    }
+    let validate = field
+        .validate
+        .as_ref()
+        .map(|validate| quote!(::prse::__private::validate(#validate(&#ident), Some(#name))?;));
+    let range = field.range.as_ref().map(|range| {
+        let range_str = range
+            .to_string()
+            .replace(" ..", "..")
+            .replace(".. ", "..")
+            .replace("..= ", "..=");
+        quote!(::prse::__private::validate_range(&#ident, #range, #name, #range_str)?;)
+    });
+    Some(quote!(#range #validate))
+}
+
+/// Creates the value from the parsed fields, validating it if needed.
+fn gen_match(
+    result: TokenStream,
+    func_idents: &[Ident],
+    value: TokenStream,
+    validate: Option<Path>,
+    error: TokenStream,
+) -> TokenStream {
+    match validate {
+        None => quote! {
+            match #result {
                 Ok(( #(#func_idents),* )) => {
-                    Ok(( #to_return ( #(#return_idents),* )))
+                    Ok(( #value ))
                 }
                 Err(e) => #error,
             }
+        },
+        Some(validate) => quote! {
+            match #result.and_then(|( #(#func_idents),* )| {
+                let __prse_value = #value;
+                ::prse::__private::validate(#validate(&__prse_value), None)?;
+                Ok(__prse_value)
+            }) {
+                Ok(__prse_value) => Ok(__prse_value),
+                Err(e) => #error,
+            }
+        },
+    }
+}
+
+fn gen_result(
+    func_name: &Ident,
+    func_idents: &[Ident],
+    conversions: Vec<TokenStream>,
+    context: bool,
+) -> TokenStream {
+    let call = if context {
+        quote!(#func_name (s, __prse_root, __prse_ctx))
+    } else {
+        quote!(#func_name (s, __prse_root))
+    };
+    if conversions.is_empty() {
+        call
+    } else {
+        quote! {
+            #call.and_then(|( #(#func_idents),* )| {
+                #(#conversions)*
+                Ok(( #(#func_idents),* ))
+            })
         }
     }
 }
 
 fn expand_unit(s: String, to_return: TokenStream, error: Option<TokenStream>) -> TokenStream {
     let l_string = s.to_token_stream();
-    let error = error.unwrap_or_else(|| {
-        if cfg!(feature = "alloc") {
-            quote! {
-                Err(::prse::ParseError::Literal {expected: (#l_string).into(), found: s.into()})
-            }
-        } else {
-            quote!(Err(::prse::ParseError::Literal))
-        }
-    });
+    let error = error.unwrap_or_else(|| quote!(Err(e)));
+    let literal_error = gen_literal_error(l_string.clone());
     quote! {
         match s {
             #l_string => Ok(#to_return),
-            _ => #error
+            _ => {
+                #[allow(unused_variables)]
+                let e = #literal_error;
+                #error
+            }
         }
     }
 }
 
-fn expand_default(mut generics: Generics, name: Ident) -> TokenStream {
+/// The error returned when the input does not contain the expected literal.
+fn gen_literal_error(expected: TokenStream) -> TokenStream {
+    if cfg!(feature = "alloc") {
+        quote!(::prse::ParseError::Literal {expected: (#expected).into(), found: s.into()})
+    } else {
+        quote!(::prse::ParseError::Literal)
+    }
+}
+
+fn expand_default(
+    mut generics: Generics,
+    name: Ident,
+    bound: &Option<Vec<WherePredicate>>,
+) -> TokenStream {
     let (impl_generics, ty_generics, where_clause) = split_for_impl(
         &mut generics,
         [
@@ -165,6 +966,8 @@ fn expand_default(mut generics: Generics, name: Ident) -> TokenStream {
                 <Self as ::core::str::FromStr>::Err: core::convert::Into<::prse::ParseError>
             ),
         ],
+        bound,
+        None,
     );
 
     quote! {
@@ -177,10 +980,86 @@ fn expand_default(mut generics: Generics, name: Ident) -> TokenStream {
     }
 }
 
-fn split_for_impl(
-    generics: &mut Generics,
+/// Implements `FromStr` and `TryFrom<&str>` using the `Parse` implementation of a type without lifetimes.
+fn expand_from_str(generics: &Generics, name: &Ident) -> TokenStream {
+    let mut from_str_generics = generics.clone();
+    from_str_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(Self: for<'__prse_a> ::prse::Parse<'__prse_a>));
+    let (impl_generics, ty_generics, where_clause) = from_str_generics.split_for_impl();
+    let from_str = quote! {
+        #[automatically_derived]
+        impl #impl_generics ::core::str::FromStr for #name #ty_generics #where_clause {
+            type Err = ::prse::ParseError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                <Self as ::prse::Parse<'_>>::from_str(s)
+            }
+        }
+    };
+
+    let mut try_from_generics = generics.clone();
+    try_from_generics.params.push(parse_quote!('__prse_a));
+    try_from_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(Self: ::prse::Parse<'__prse_a>));
+    let (impl_generics, _, where_clause) = try_from_generics.split_for_impl();
+    quote! {
+        #from_str
+
+        #[automatically_derived]
+        impl #impl_generics ::core::convert::TryFrom<&'__prse_a str> for #name #ty_generics #where_clause {
+            type Error = ::prse::ParseError;
+
+            fn try_from(s: &'__prse_a str) -> Result<Self, Self::Error> {
+                <Self as ::prse::Parse<'__prse_a>>::from_str(s)
+            }
+        }
+    }
+}
+
+/// Implements `ParseWith` for any context using the `Parse` implementation, ignoring the context.
+///
+/// This is only generated for types without a context attribute, which implement `ParseWith` for their context instead.
+fn expand_parse_with(generics: &Generics, name: &Ident) -> TokenStream {
+    let ty_generics = generics.split_for_impl().1;
+    let mut generics = generics.clone();
+    generics.params.push(parse_quote!('__prse_a));
+    generics.params.push(parse_quote!(__PrseC: ?Sized));
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(Self: ::prse::Parse<'__prse_a>));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics ::prse::ParseWith<'__prse_a, __PrseC> for #name #ty_generics #where_clause {
+            fn from_str_with(s: &'__prse_a str, _: &mut __PrseC) -> Result<Self, ::prse::ParseError> {
+                <Self as ::prse::Parse<'__prse_a>>::from_str(s)
+            }
+
+            fn from_str_with_in(
+                s: &'__prse_a str,
+                __prse_root: &'__prse_a str,
+                _: &mut __PrseC,
+            ) -> Result<Self, ::prse::ParseError> {
+                <Self as ::prse::Parse<'__prse_a>>::from_str_in(s, __prse_root)
+            }
+        }
+    }
+}
+
+/// Adds the `'__prse_a` lifetime to the generics, along with the given predicates
+/// and a `Parse` bound on each type parameter unless they are replaced by `bound`.
+/// With a context, each type parameter is bounded by `ParseWith` instead.
+fn split_for_impl<'a>(
+    generics: &'a mut Generics,
     extra_predicates: impl IntoIterator<Item = WherePredicate>,
-) -> (ImplGenerics, TokenStream, Option<&WhereClause>) {
+    bound: &Option<Vec<WherePredicate>>,
+    context: Option<&Type>,
+) -> (ImplGenerics<'a>, TokenStream, Option<&'a WhereClause>) {
     let ty_generics = generics.split_for_impl().1.to_token_stream();
 
     generics.params.push(GenericParam::Lifetime(LifetimeParam {
@@ -190,18 +1069,24 @@ fn split_for_impl(
         bounds: generics.lifetimes().map(|l| l.lifetime.clone()).collect(),
     }));
 
-    let type_predicates: Vec<WherePredicate> = generics
-        .params
-        .iter()
-        .filter_map(|p| {
-            if let GenericParam::Type(t) = p {
-                let t = &t.ident;
-                Some(parse_quote!(#t: ::prse::Parse<'__prse_a>))
-            } else {
-                None
-            }
-        })
-        .collect();
+    let type_predicates: Vec<WherePredicate> = match bound {
+        Some(bound) => bound.clone(),
+        None => generics
+            .params
+            .iter()
+            .filter_map(|p| {
+                if let GenericParam::Type(t) = p {
+                    let t = &t.ident;
+                    Some(match context {
+                        Some(context) => parse_quote!(#t: ::prse::ParseWith<'__prse_a, #context>),
+                        None => parse_quote!(#t: ::prse::Parse<'__prse_a>),
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect(),
+    };
 
     let predicates = &mut generics.make_where_clause().predicates;
     predicates.extend(extra_predicates);
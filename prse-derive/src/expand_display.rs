@@ -0,0 +1,281 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::ToTokens;
+use std::collections::BTreeSet;
+use syn::{GenericParam, Generics, Index, Member, WherePredicate};
+
+use crate::attributes::Field;
+use crate::derive::{Derive, Fields, KeyValue, Tagged, Tokens};
+use crate::instructions::{Instruction, Separator};
+use crate::var::Var;
+
+impl Derive {
+    /// Generates an implementation of `Display` that writes the value back using its first pattern.
+    pub fn into_display_tokens(self) -> syn::Result<TokenStream> {
+        let (generics, name, body) = match self {
+            Derive::NoAttributes(_, name) => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "The Display derive macro requires a prse attribute.",
+                ))
+            }
+            Derive::Struct(g, name, f) => {
+                let (bindings, body) = display_fields(&name, f.first().unwrap())?;
+                let body = quote! {
+                    let Self { #bindings } = self;
+                    #body
+                };
+                (g, name, body)
+            }
+            Derive::Enum(g, name, v, _, discriminants, _) => {
+                let mut seen = BTreeSet::new();
+                let mut arms = vec![];
+                // The first pattern of each variant that is tried is the one used to display it.
+                for (variant, f) in v.iter() {
+                    if !seen.insert(variant.to_string()) {
+                        continue;
+                    }
+                    let (bindings, body) = display_fields(variant, f)?;
+                    arms.push(quote!(Self::#variant { #bindings } => { #body }));
+                }
+                // Variants without a pattern are written as their discriminant.
+                for variant in discriminants.iter().flatten() {
+                    if seen.insert(variant.to_string()) {
+                        arms.push(quote! {
+                            Self::#variant => ::core::write!(__prse_f, "{}", Self::#variant as i128)?,
+                        });
+                    }
+                }
+                let body = quote! {
+                    match self {
+                        #(#arms)*
+                    }
+                };
+                (g, name, body)
+            }
+            Derive::KeyValue(g, name, kv) => {
+                let body = display_key_value(kv)?;
+                (g, name, body)
+            }
+            Derive::Tagged(g, name, tagged) => {
+                let body = display_tagged(tagged)?;
+                (g, name, body)
+            }
+            Derive::Tokens(g, name, tokens) => {
+                let body = display_tokens(tokens, &name)?;
+                (g, name, body)
+            }
+        };
+        let mut generics = generics;
+        let (impl_generics, ty_generics, where_clause) = split_for_display(&mut generics);
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics ::core::fmt::Display for #name #ty_generics #where_clause {
+                #[allow(unused_assignments)]
+                fn fmt(&self, __prse_f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #body
+                    Ok(())
+                }
+            }
+        })
+    }
+}
+
+/// Returns the pattern binding each used field and the code writing the pattern of the fields.
+fn display_fields(ident: &Ident, f: &Fields) -> syn::Result<(TokenStream, TokenStream)> {
+    let (instructions, fields, is_named) = match f {
+        Fields::Named(instructions, fields, _) => (instructions, fields, true),
+        Fields::Unnamed(instructions, fields, _) => (instructions, fields, false),
+        Fields::Unit(s) => return Ok((quote!(..), quote!(__prse_f.write_str(#s)?;))),
+        Fields::Any => {
+            return Err(syn::Error::new(
+                ident.span(),
+                "A unit variant with the other attribute cannot be displayed.",
+            ))
+        }
+    };
+    let parsed_fields: Vec<_> = (0..fields.len()).filter(|&i| !fields[i].skip).collect();
+    let mut used = BTreeSet::new();
+    let mut values = vec![];
+    let mut count = 0;
+    for i in instructions.0.iter() {
+        let idx = match i.get_var() {
+            None => continue,
+            Some(Var::Ident(name) | Var::Field(name, _)) if is_named => fields
+                .iter()
+                .position(|f| !f.skip && f.has_name(name))
+                .unwrap(),
+            Some(Var::Position(p)) => parsed_fields[*p as usize],
+            Some(_) => {
+                count += 1;
+                parsed_fields[count - 1]
+            }
+        };
+        used.insert(idx);
+        let binding = format_ident!("__prse_field_{idx}");
+        values.push(match i {
+            Instruction::IterParse(..) => {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "Iterator captures cannot be displayed.",
+                ))
+            }
+            Instruction::VecParse(..) | Instruction::MultiParse(..)
+                if fields[idx].display_with.is_some() =>
+            {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "The display_with attribute can only be used with a single capture.",
+                ))
+            }
+            _ => match i.get_var() {
+                Some(Var::Field(_, path)) => quote!(&#binding #(.#path)*),
+                _ => display_value(&fields[idx], binding.to_token_stream(), ident.span())?,
+            },
+        });
+    }
+
+    let bindings = used.into_iter().map(|idx| {
+        let member = member(&fields[idx], idx);
+        let binding = format_ident!("__prse_field_{idx}");
+        quote!(#member: #binding,)
+    });
+    Ok((quote!(#(#bindings)* ..), instructions.gen_display(values)))
+}
+
+/// Writes the tag pattern of each variant using its tag value and its first pattern.
+fn display_tagged(tagged: Tagged) -> syn::Result<TokenStream> {
+    let mut arms = vec![];
+    for (variant, value, fields) in tagged.variants.iter() {
+        if tagged.is_bare_unit(fields) {
+            arms.push(quote!(Self::#variant { .. } => __prse_f.write_str(#value)?,));
+            continue;
+        }
+        let (bindings, body) = display_fields(variant, &fields[0])?;
+        let parts = tagged.instructions.0.iter().map(|i| match i {
+            Instruction::Lit(l) => quote!(__prse_f.write_str(#l)?;),
+            Instruction::Parse(Var::Implied) => body.clone(),
+            _ => quote!(__prse_f.write_str(#value)?;),
+        });
+        arms.push(quote!(Self::#variant { #bindings } => { #(#parts)* }));
+    }
+    Ok(quote! {
+        match self {
+            #(#arms)*
+        }
+    })
+}
+
+/// Writes each field as a key-value pair, skipping optional fields that are `None`.
+fn display_key_value(kv: KeyValue) -> syn::Result<TokenStream> {
+    let KeyValue {
+        fields,
+        sep,
+        assign,
+        ..
+    } = kv;
+    let mut bindings = vec![];
+    let mut pairs = vec![];
+    for (idx, field) in fields.iter().enumerate().filter(|(_, f)| !f.skip) {
+        let ident = field.ident.as_ref().unwrap();
+        let binding = format_ident!("__prse_field_{idx}");
+        let key = field.name().unwrap();
+        bindings.push(quote!(#ident: #binding,));
+        let pair = |value: TokenStream| -> syn::Result<TokenStream> {
+            let value = display_value(field, value, ident.span())?;
+            Ok(quote! {
+                __prse_f.write_str(__prse_sep)?;
+                __prse_f.write_str(#key)?;
+                __prse_f.write_str(#assign)?;
+                ::core::write!(__prse_f, "{}", #value)?;
+                __prse_sep = #sep;
+            })
+        };
+        pairs.push(if crate::expand_derive::is_option(&field.ty) {
+            let pair = pair(quote!(__prse_value))?;
+            quote! {
+                if let Some(__prse_value) = #binding {
+                    #pair
+                }
+            }
+        } else {
+            pair(binding.to_token_stream())?
+        });
+    }
+    Ok(quote! {
+        let Self { #(#bindings)* .. } = self;
+        let mut __prse_sep = "";
+        #(#pairs)*
+    })
+}
+
+/// Writes each field in declaration order, separated by the separator.
+fn display_tokens(tokens: Tokens, name: &Ident) -> syn::Result<TokenStream> {
+    let sep = match &tokens.sep {
+        Separator::Str(s) => s.as_str(),
+        _ => " ",
+    };
+    let mut bindings = vec![];
+    let mut items = vec![];
+    for (idx, field) in tokens.fields.iter().enumerate().filter(|(_, f)| !f.skip) {
+        let member = member(field, idx);
+        let binding = format_ident!("__prse_field_{idx}");
+        bindings.push(quote!(#member: #binding,));
+        let value = display_value(field, binding.to_token_stream(), name.span())?;
+        if !items.is_empty() {
+            items.push(quote!(__prse_f.write_str(#sep)?;));
+        }
+        items.push(quote!(::core::write!(__prse_f, "{}", #value)?;));
+    }
+    Ok(quote! {
+        let Self { #(#bindings)* .. } = self;
+        #(#items)*
+    })
+}
+
+/// Returns a reference to a value that displays the field, using its `display_with` function if it has one.
+fn display_value(field: &Field, value: TokenStream, span: Span) -> syn::Result<TokenStream> {
+    match (&field.display_with, &field.with) {
+        (Some(display_with), _) => Ok(quote! {
+            &::prse::__private::display_fn(|__prse_f| #display_with(#value, __prse_f))
+        }),
+        (None, Some(_)) => Err(syn::Error::new(
+            field.ident.as_ref().map_or(span, Ident::span),
+            "A field with the with attribute needs a display_with attribute to be displayed.",
+        )),
+        (None, None) => Ok(value),
+    }
+}
+
+fn member(field: &Field, idx: usize) -> Member {
+    match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(idx)),
+    }
+}
+
+fn split_for_display(
+    generics: &mut Generics,
+) -> (
+    syn::ImplGenerics<'_>,
+    syn::TypeGenerics<'_>,
+    Option<&syn::WhereClause>,
+) {
+    let type_predicates: Vec<WherePredicate> = generics
+        .params
+        .iter()
+        .filter_map(|p| {
+            if let GenericParam::Type(t) = p {
+                let t = &t.ident;
+                Some(parse_quote!(#t: ::core::fmt::Display))
+            } else {
+                None
+            }
+        })
+        .collect();
+    generics
+        .make_where_clause()
+        .predicates
+        .extend(type_predicates);
+    generics.split_for_impl()
+}
@@ -0,0 +1,226 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::ToTokens;
+use syn::ext::IdentExt;
+use syn::Type;
+
+use crate::attributes::Field;
+use crate::derive::{Derive, Fields, KeyValue, Tagged, Tokens};
+use crate::instructions::Instruction;
+use crate::var::Var;
+
+/// A pattern of the derived type along with the fields it parses.
+struct VariantSchema {
+    name: String,
+    pattern: String,
+    prefix: String,
+    fields: Vec<(String, String)>,
+}
+
+impl Derive {
+    /// Generates the `PRSE_PATTERNS` and `PRSE_SCHEMA` constants describing the patterns of the type.
+    pub fn schema_tokens(&self) -> TokenStream {
+        let (generics, name) = self.header();
+        let (variants, discriminant) = match self {
+            Derive::NoAttributes(..) => (vec![], false),
+            Derive::Struct(_, name, f) => {
+                (f.iter().map(|f| fields_schema(name, f)).collect(), false)
+            }
+            Derive::Enum(_, _, v, _, discriminants, _) => (
+                v.iter()
+                    .map(|(variant, f)| fields_schema(variant, f))
+                    .collect(),
+                discriminants.is_some(),
+            ),
+            Derive::KeyValue(_, name, kv) => (vec![key_value_schema(name, kv)], false),
+            Derive::Tokens(_, name, tokens) => (vec![tokens_schema(name, tokens)], false),
+            Derive::Tagged(_, _, tagged) => (tagged_schema(tagged), false),
+        };
+
+        let patterns = variants.iter().map(|v| &v.pattern);
+        let variants = variants.iter().map(|v| {
+            let VariantSchema {
+                name,
+                pattern,
+                prefix,
+                fields,
+            } = v;
+            let fields = fields
+                .iter()
+                .map(|(name, ty)| quote!(::prse::SchemaField { name: #name, ty: #ty }));
+            quote! {
+                ::prse::SchemaVariant {
+                    name: #name,
+                    pattern: #pattern,
+                    prefix: #prefix,
+                    fields: &[#(#fields),*],
+                }
+            }
+        });
+        let type_name = name.unraw().to_string();
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            #[automatically_derived]
+            impl #impl_generics #name #ty_generics #where_clause {
+                #[doc = "The pattern of each variant in the order they are tried when parsing."]
+                pub const PRSE_PATTERNS: &'static [&'static str] = &[#(#patterns),*];
+                #[doc = "A description of the input that can be parsed into this type."]
+                pub const PRSE_SCHEMA: ::prse::Schema = ::prse::Schema {
+                    name: #type_name,
+                    variants: &[#(#variants),*],
+                    discriminant: #discriminant,
+                };
+            }
+        }
+    }
+}
+
+fn fields_schema(ident: &Ident, f: &Fields) -> VariantSchema {
+    let name = ident.unraw().to_string();
+    let (instructions, fields) = match f {
+        Fields::Named(instructions, fields, _) | Fields::Unnamed(instructions, fields, _) => {
+            (instructions, fields)
+        }
+        Fields::Unit(s) => {
+            return VariantSchema {
+                name,
+                pattern: s.replace('{', "{{").replace('}', "}}"),
+                prefix: s.clone(),
+                fields: vec![],
+            }
+        }
+        Fields::Any => {
+            return VariantSchema {
+                name,
+                pattern: String::from("{}"),
+                prefix: String::new(),
+                fields: vec![],
+            }
+        }
+    };
+    VariantSchema {
+        name,
+        pattern: instructions.pattern(),
+        prefix: instructions.prefix().to_string(),
+        fields: parsed_fields(fields),
+    }
+}
+
+/// Each pattern of each variant, with the tag replaced by the tag value of the variant.
+fn tagged_schema(tagged: &Tagged) -> Vec<VariantSchema> {
+    let escape = |s: &str| s.replace('{', "{{").replace('}', "}}");
+    let mut schemas = vec![];
+    for (variant, value, fields) in tagged.variants.iter() {
+        for f in fields {
+            let rest = fields_schema(variant, f);
+            if tagged.is_bare_unit(fields) {
+                schemas.push(VariantSchema {
+                    pattern: escape(value),
+                    prefix: value.clone(),
+                    ..rest
+                });
+                continue;
+            }
+            let mut pattern = String::new();
+            let mut prefix = String::new();
+            let mut in_prefix = true;
+            for i in tagged.instructions.0.iter() {
+                let (part, literal) = match i {
+                    Instruction::Lit(l) => (escape(l), l.as_str()),
+                    Instruction::Parse(Var::Implied) => {
+                        pattern.push_str(&rest.pattern);
+                        if in_prefix {
+                            prefix.push_str(&rest.prefix);
+                        }
+                        in_prefix = false;
+                        continue;
+                    }
+                    _ => (escape(value), value.as_str()),
+                };
+                pattern.push_str(&part);
+                if in_prefix {
+                    prefix.push_str(literal);
+                }
+            }
+            schemas.push(VariantSchema {
+                pattern,
+                prefix,
+                ..rest
+            });
+        }
+    }
+    schemas
+}
+
+fn key_value_schema(name: &Ident, kv: &KeyValue) -> VariantSchema {
+    let escape = |s: &str| s.replace('{', "{{").replace('}', "}}");
+    let fields = parsed_fields(&kv.fields);
+    let pattern = fields
+        .iter()
+        .map(|(key, _)| format!("{}{}{{{key}}}", escape(key), escape(&kv.assign)))
+        .collect::<Vec<_>>()
+        .join(&escape(&kv.sep));
+    VariantSchema {
+        name: name.unraw().to_string(),
+        pattern,
+        prefix: String::new(),
+        fields,
+    }
+}
+
+fn tokens_schema(name: &Ident, tokens: &Tokens) -> VariantSchema {
+    let sep = tokens
+        .sep
+        .display_str()
+        .replace('{', "{{")
+        .replace('}', "}}");
+    let fields = parsed_fields(&tokens.fields);
+    let pattern = fields
+        .iter()
+        .map(|(name, _)| format!("{{{name}}}"))
+        .collect::<Vec<_>>()
+        .join(&sep);
+    VariantSchema {
+        name: name.unraw().to_string(),
+        pattern,
+        prefix: String::new(),
+        fields,
+    }
+}
+
+/// The name and type name of each field that is not skipped.
+///
+/// Tuple fields are named by their position in the pattern.
+fn parsed_fields(fields: &[Field]) -> Vec<(String, String)> {
+    fields
+        .iter()
+        .filter(|f| !f.skip)
+        .enumerate()
+        .map(|(idx, f)| {
+            (
+                f.name().unwrap_or_else(|| idx.to_string()),
+                type_name(&f.ty),
+            )
+        })
+        .collect()
+}
+
+/// The type as it was written, without the spaces added between its tokens.
+fn type_name(ty: &Type) -> String {
+    let tokens = ty.to_token_stream().to_string();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+    let mut res = String::new();
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ' ' {
+            let next = chars.peek().copied().unwrap_or(' ');
+            let prev = res.chars().last().unwrap_or(' ');
+            if (is_word(prev) && is_word(next)) || prev == ',' || prev == ';' {
+                res.push(' ');
+            }
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
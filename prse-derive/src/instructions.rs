@@ -5,14 +5,142 @@ use itertools::Itertools;
 use proc_macro2::Span;
 use proc_macro2::{Ident, TokenStream};
 use quote::{ToTokens, TokenStreamExt};
+use std::iter;
+use syn::ext::IdentExt;
+
+const POSITIONS_ERROR: &str =
+    "Each positional argument must uniquely map to a corresponding index in the returned tuple.";
 
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
 pub enum Instruction {
     Lit(String),
     Parse(Var),
-    VecParse(Var, String, bool),
-    IterParse(Var, String, bool),
-    MultiParse(Var, String, u8, bool),
+    /// A capture of a fixed width, which trims the spaces used to pad it when the flag is set.
+    FixedParse(Var, Width, bool),
+    VecParse(Var, Repetition, Modifiers),
+    IterParse(Var, Repetition, Modifiers),
+    MultiParse(Var, Repetition, u8, Modifiers),
+}
+
+#[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub enum Width {
+    Bytes(usize),
+    Chars(usize),
+}
+
+impl Width {
+    fn to_args(self) -> TokenStream {
+        match self {
+            Width::Bytes(w) => quote!(#w, false),
+            Width::Chars(w) => quote!(#w, true),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub struct Modifiers {
+    /// Skip empty items, set with `!`.
+    pub is_multi: bool,
+    /// Ignore separators inside brackets and quotes, set with `#`.
+    pub is_nested: bool,
+}
+
+/// How the items of a repetition are split up.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub enum Repetition {
+    Separated(Separator),
+    /// Fixed size chunks of chars, set with `~<size>`.
+    Chunk(usize),
+}
+
+impl Repetition {
+    pub(crate) fn is_empty(&self) -> bool {
+        matches!(self, Repetition::Separated(Separator::Str(s)) if s.is_empty())
+    }
+
+    /// The string written between each item when displaying a repetition.
+    pub(crate) fn display_str(&self) -> &str {
+        match self {
+            Repetition::Separated(sep) => sep.display_str(),
+            Repetition::Chunk(_) => "",
+        }
+    }
+
+    /// The repetition written using the syntax of a pattern.
+    fn pattern_str(&self) -> String {
+        match self {
+            Repetition::Separated(sep) => sep.pattern_str(),
+            Repetition::Chunk(size) => format!("~{size}"),
+        }
+    }
+}
+
+impl From<Separator> for Repetition {
+    fn from(sep: Separator) -> Self {
+        Repetition::Separated(sep)
+    }
+}
+
+impl From<&str> for Repetition {
+    fn from(s: &str) -> Self {
+        Repetition::Separated(s.into())
+    }
+}
+
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub enum Separator {
+    Str(String),
+    AnyOf(Vec<String>),
+    Whitespace,
+}
+
+impl Separator {
+    /// The string written between each item when displaying a repetition.
+    pub(crate) fn display_str(&self) -> &str {
+        match self {
+            Separator::Str(s) => s,
+            Separator::AnyOf(v) => &v[0],
+            Separator::Whitespace => " ",
+        }
+    }
+}
+
+impl Separator {
+    /// The separator written using the syntax of a pattern.
+    fn pattern_str(&self) -> String {
+        match self {
+            Separator::Str(s) if s.starts_with(['[', '~', '\\']) => format!("\\{s}"),
+            Separator::Str(s) => s.clone(),
+            Separator::AnyOf(v) => {
+                let escaped = v.iter().map(|s| {
+                    s.chars()
+                        .flat_map(|c| match c {
+                            '\\' | '|' | ']' => vec!['\\', c],
+                            c => vec![c],
+                        })
+                        .collect::<String>()
+                });
+                format!("[{}]", escaped.format("|"))
+            }
+            Separator::Whitespace => String::from("\\s"),
+        }
+    }
+}
+
+impl From<&str> for Separator {
+    fn from(s: &str) -> Self {
+        Separator::Str(String::from(s))
+    }
+}
+
+impl ToTokens for Separator {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append_all(match self {
+            Separator::Str(s) => quote!(::prse::Separator::Str(#s)),
+            Separator::AnyOf(v) => quote!(::prse::Separator::AnyOf(&[ #(#v),* ])),
+            Separator::Whitespace => quote!(::prse::Separator::Whitespace),
+        });
+    }
 }
 
 impl Instruction {
@@ -20,36 +148,107 @@ impl Instruction {
         match self {
             Instruction::Lit(_) => None,
             Instruction::Parse(v)
+            | Instruction::FixedParse(v, ..)
             | Instruction::VecParse(v, ..)
             | Instruction::IterParse(v, ..)
             | Instruction::MultiParse(v, ..) => Some(v),
         }
     }
 
-    pub(crate) fn gen_iter(&self) -> Option<TokenStream> {
+    /// Returns this instruction with its variable replaced, so that captures can be compared.
+    pub(crate) fn without_var(&self) -> Instruction {
+        let mut res = self.clone();
+        match &mut res {
+            Instruction::Lit(_) => {}
+            Instruction::Parse(v)
+            | Instruction::FixedParse(v, ..)
+            | Instruction::VecParse(v, ..)
+            | Instruction::IterParse(v, ..)
+            | Instruction::MultiParse(v, ..) => *v = Var::Implied,
+        }
+        res
+    }
+
+    pub(crate) fn is_nested(&self) -> bool {
         match self {
+            Instruction::VecParse(_, _, modifiers)
+            | Instruction::IterParse(_, _, modifiers)
+            | Instruction::MultiParse(_, _, _, modifiers) => modifiers.is_nested,
+            _ => false,
+        }
+    }
+
+    /// Generates the iterator of a repetition.
+    ///
+    /// With a context, the repetitions split the input into string slices
+    /// which are then parsed using the context `__prse_ctx`.
+    pub(crate) fn gen_iter(&self, context: bool) -> Option<TokenStream> {
+        let item = context.then(|| quote!(::<&str>));
+        let iter = match self {
             Instruction::VecParse(_, sep, _)
             | Instruction::IterParse(_, sep, _)
             | Instruction::MultiParse(_, sep, ..)
                 if sep.is_empty() =>
             {
-                Some(quote! {
-                    ::prse::ParseChars::new(__prse_parse)
-                })
+                quote! {
+                    ::prse::ParseChars #item ::new(__prse_parse).in_input(__prse_root)
+                }
             }
-            Instruction::VecParse(_, sep, is_multi)
-            | Instruction::IterParse(_, sep, is_multi)
-            | Instruction::MultiParse(_, sep, _, is_multi) => Some(quote! {
-                ::prse::ParseIter::new(__prse_parse, #sep, #is_multi)
-            }),
-            _ => None,
-        }
+            Instruction::VecParse(_, Repetition::Chunk(size), _)
+            | Instruction::IterParse(_, Repetition::Chunk(size), _)
+            | Instruction::MultiParse(_, Repetition::Chunk(size), ..) => quote! {
+                ::prse::ParseChunks #item ::new(__prse_parse, #size)?.in_input(__prse_root)
+            },
+            Instruction::VecParse(_, Repetition::Separated(sep), modifiers)
+            | Instruction::IterParse(_, Repetition::Separated(sep), modifiers)
+            | Instruction::MultiParse(_, Repetition::Separated(sep), _, modifiers) => {
+                let skip_empty = modifiers.is_multi.then(|| quote!(.skip_empty()));
+                let nested = modifiers.is_nested.then(|| quote!(.nested()));
+                quote! {
+                    ::prse::ParseIter #item ::new(__prse_parse, #sep) #skip_empty #nested .in_input(__prse_root)
+                }
+            }
+            _ => return None,
+        };
+        Some(if context {
+            quote! {
+                #iter.map(|__prse_item| ::prse::__private::parse_item_with(__prse_item, __prse_parse, __prse_root, __prse_ctx))
+            }
+        } else {
+            iter
+        })
     }
 }
 
 #[derive(Ord, PartialOrd, Eq, PartialEq, Debug, Clone)]
 pub(crate) struct Instructions(pub Vec<Instruction>);
 
+fn check_ambiguity(
+    previous: &[Instruction],
+    instruction: &Instruction,
+    input_span: Span,
+) -> syn::Result<()> {
+    // Fixed width captures can be next to any capture, as long as it is not
+    // surrounded by captures of an unknown width.
+    let is_ambiguous = !matches!(
+        instruction,
+        Instruction::Lit(_) | Instruction::FixedParse(..)
+    ) && !matches!(
+        previous
+            .iter()
+            .rev()
+            .find(|i| !matches!(i, Instruction::FixedParse(..))),
+        Some(Instruction::Lit(_)) | None
+    );
+    if is_ambiguous {
+        return Err(syn::Error::new(
+            input_span,
+            "Cannot have two captures without a string in between.",
+        ));
+    }
+    Ok(())
+}
+
 impl Instructions {
     pub fn new(input: &str, input_span: Span) -> syn::Result<Instructions> {
         let mut i = input.chars().multipeek();
@@ -101,13 +300,9 @@ impl Instructions {
                             continue;
                         }
                     }
-                    if !matches!(instructions.last(), Some(Instruction::Lit(_)) | None) {
-                        return Err(syn::Error::new(
-                            input_span,
-                            "Cannot have two captures without a string in between.",
-                        ));
-                    }
-                    instructions.push(var::parse_var(val, input_span)?);
+                    let instruction = var::parse_var(val, input_span)?;
+                    check_ambiguity(&instructions, &instruction, input_span)?;
+                    instructions.push(instruction);
                     val = String::new();
                     var_mode = false;
                 }
@@ -127,6 +322,104 @@ impl Instructions {
         Self::validate_instructions(instructions, input_span)
     }
 
+    /// Replaces the single `{}` capture of this enum pattern with a variant's instructions.
+    pub fn substitute(&self, variant: Instructions, input_span: Span) -> syn::Result<Instructions> {
+        let mut instructions: Vec<Instruction> = vec![];
+        let parts = self.0.iter().flat_map(|i| match i {
+            Instruction::Parse(Var::Implied) => variant.0.clone(),
+            i => vec![i.clone()],
+        });
+        for instruction in parts {
+            match (instructions.last_mut(), instruction) {
+                (Some(Instruction::Lit(prev)), Instruction::Lit(l)) => prev.push_str(&l),
+                (_, instruction) => {
+                    check_ambiguity(&instructions, &instruction, input_span)?;
+                    instructions.push(instruction);
+                }
+            }
+        }
+        Self::validate_instructions(instructions, input_span)
+    }
+
+    /// The pattern that these instructions were created from, using the syntax of the `parse!` macro.
+    pub fn pattern(&self) -> String {
+        let escape = |s: &str| s.replace('{', "{{").replace('}', "}}");
+        let var = |v: &Var| match v {
+            Var::Implied => String::new(),
+            Var::Ident(i) => i.unraw().to_string(),
+            Var::Position(p) => p.to_string(),
+            Var::Field(i, path) => iter::once(i)
+                .chain(path)
+                .map(|i| i.unraw().to_string())
+                .join("."),
+        };
+        let repetition = |v: &Var, sep: &Repetition, count: Option<u8>, modifiers: &Modifiers| {
+            let multi = if modifiers.is_multi { "!" } else { "" };
+            let nested = if modifiers.is_nested { "#" } else { "" };
+            let count = count.map(|c| c.to_string()).unwrap_or_default();
+            escape(&format!(
+                "{}:{}:{multi}{nested}{count}",
+                var(v),
+                sep.pattern_str()
+            ))
+        };
+        self.0
+            .iter()
+            .map(|i| match i {
+                Instruction::Lit(l) => escape(l),
+                Instruction::Parse(v) => format!("{{{}}}", escape(&var(v))),
+                Instruction::FixedParse(v, width, trim) => {
+                    let trim = if *trim { "t" } else { "" };
+                    match width {
+                        Width::Bytes(w) => format!("{{{}:b{w}{trim}}}", var(v)),
+                        Width::Chars(w) => format!("{{{}:w{w}{trim}}}", var(v)),
+                    }
+                }
+                Instruction::VecParse(v, sep, modifiers) => {
+                    format!("{{{}}}", repetition(v, sep, None, modifiers))
+                }
+                Instruction::IterParse(v, sep, modifiers) => {
+                    format!("{{{}}}", repetition(v, sep, Some(0), modifiers))
+                }
+                Instruction::MultiParse(v, sep, count, modifiers) => {
+                    format!("{{{}}}", repetition(v, sep, Some(*count), modifiers))
+                }
+            })
+            .collect()
+    }
+
+    /// The literal text at the start of the pattern.
+    pub fn prefix(&self) -> &str {
+        match self.0.first() {
+            Some(Instruction::Lit(l)) => l,
+            _ => "",
+        }
+    }
+
+    /// Checks that an enum pattern contains a single `{}` capture and only named captures otherwise.
+    pub fn validate_enum_pattern(&self, input_span: Span) -> syn::Result<()> {
+        let mut implied = 0;
+        for i in self.0.iter() {
+            match (i, i.get_var()) {
+                (Instruction::Parse(Var::Implied), _) => implied += 1,
+                (_, Some(Var::Ident(_) | Var::Field(..)) | None) => {}
+                _ => {
+                    return Err(syn::Error::new(
+                        input_span,
+                        "An enum prse attribute can only contain named variables and a single {}.",
+                    ))
+                }
+            }
+        }
+        if implied != 1 {
+            return Err(syn::Error::new(
+                input_span,
+                "An enum prse attribute must contain a single {} to be replaced by each variant's pattern.",
+            ));
+        }
+        Ok(())
+    }
+
     fn validate_instructions(
         instructions: Vec<Instruction>,
         input_span: Span,
@@ -139,19 +432,17 @@ impl Instructions {
                 .iter()
                 .any(|i| matches!(i.get_var(), Some(Var::Implied)))
             {
-                let has_constant_step = instructions
+                let is_unique = instructions
                     .iter()
                     .flat_map(|i| match i.get_var() {
                         Some(Var::Position(p)) => Some(p),
                         _ => None,
                     })
-                    .sorted()
-                    .zip(0_u8..)
-                    .all(|(i, p)| i == &p);
-                if has_constant_step {
+                    .all_unique();
+                if is_unique {
                     Ok(Instructions(instructions))
                 } else {
-                    Err(syn::Error::new(input_span, "Each positional argument much uniquely map to a corresponding index in the returned tuple."))
+                    Err(syn::Error::new(input_span, POSITIONS_ERROR))
                 }
             } else {
                 Err(syn::Error::new(
@@ -164,7 +455,44 @@ impl Instructions {
         }
     }
 
-    pub fn gen_function(&self, body: TokenStream, func_name: Ident) -> TokenStream {
+    /// Checks that the positional arguments map to every index of the returned tuple.
+    ///
+    /// The derive macro allows gaps, which are filled by the default value of their field.
+    pub fn validate_positions(&self, input_span: Span) -> syn::Result<()> {
+        let has_constant_step = self
+            .0
+            .iter()
+            .flat_map(|i| match i.get_var() {
+                Some(Var::Position(p)) => Some(p),
+                _ => None,
+            })
+            .sorted()
+            .zip(0_u8..)
+            .all(|(i, p)| i == &p);
+        if has_constant_step {
+            Ok(())
+        } else {
+            Err(syn::Error::new(input_span, POSITIONS_ERROR))
+        }
+    }
+
+    /// Checks that there are no iterator captures, as they are parsed lazily and so cannot use a context.
+    pub fn validate_context(&self, span: Span) -> syn::Result<()> {
+        if self
+            .0
+            .iter()
+            .any(|i| matches!(i, Instruction::IterParse(..)))
+        {
+            Err(syn::Error::new(span, "Iterator captures cannot be used with a context, as they are parsed lazily. Use a Vec or an array instead."))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Generates the function `func_name` which parses `__prse_input` and returns each capture.
+    ///
+    /// With a context, the function also takes the context `__prse_ctx` which is passed to each capture.
+    pub fn gen_function(&self, body: TokenStream, func_name: Ident, context: bool) -> TokenStream {
         let mut return_types = vec![];
         let mut generics = vec![];
         for (idx, i) in self
@@ -175,7 +503,7 @@ impl Instructions {
         {
             let type_ident = format_ident!("T{idx}");
             return_types.push(match i {
-                Instruction::Parse(_) => type_ident.to_token_stream(),
+                Instruction::Parse(_) | Instruction::FixedParse(..) => type_ident.to_token_stream(),
                 Instruction::VecParse(..) => {
                     if cfg!(feature = "std") {
                         quote!(::std::vec::Vec<#type_ident>)
@@ -186,6 +514,9 @@ impl Instructions {
                 Instruction::IterParse(_, sep, _) if sep.is_empty() => quote! {
                    ::prse::ParseChars<'a, #type_ident>
                 },
+                Instruction::IterParse(_, Repetition::Chunk(_), _) => quote! {
+                   ::prse::ParseChunks<'a, #type_ident>
+                },
                 Instruction::IterParse(..) => quote! {
                    ::prse::ParseIter<'a, #type_ident>
                 },
@@ -198,66 +529,128 @@ impl Instructions {
             generics.push(type_ident);
         }
 
+        if !context {
+            return quote! {
+                fn #func_name <'a, #(#generics: Parse<'a>),* >(
+                    __prse_input: &'a str,
+                    __prse_root: &'a str,
+                ) -> ::core::result::Result<( #(#return_types),* ), ::prse::ParseError> {
+                    #body
+                }
+            };
+        }
+
         quote! {
-            fn #func_name <'a, #(#generics: Parse<'a>),* >(
+            fn #func_name <'a, __PrseC: ?Sized, #(#generics: ::prse::ParseWith<'a, __PrseC>),* >(
                 __prse_input: &'a str,
+                __prse_root: &'a str,
+                __prse_ctx: &mut __PrseC,
             ) -> ::core::result::Result<( #(#return_types),* ), ::prse::ParseError> {
                 #body
             }
         }
     }
 
-    pub fn gen_body(&self, result: &mut TokenStream) {
+    pub fn gen_body(&self, result: &mut TokenStream, context: bool) {
         let mut store_token = None;
+        let mut trailing_fixed = vec![];
         let alloc_crate: TokenStream = if cfg!(feature = "std") {
             quote!(std)
         } else {
             quote!(alloc)
         };
 
+        let parse = |slice: TokenStream| {
+            if context {
+                quote!(::prse::__private::try_parse_context_with(#slice, __prse_input, __prse_root, __prse_ctx))
+            } else {
+                quote!(::prse::__private::try_parse_context(#slice, __prse_input, __prse_root))
+            }
+        };
+        // The spaces used to pad a value to its width are only removed when asked for.
+        let trimmed = |slice: TokenStream, trim: bool| {
+            if trim {
+                quote!(#slice.trim_end_matches(' '))
+            } else {
+                slice
+            }
+        };
+
         for (idx, i) in self.0.iter().enumerate() {
             let var = format_ident!("__prse_{idx}");
             match i {
                 Instruction::Lit(l_string) => {
+                    // The literal after a nested repetition must also skip over brackets and quotes.
+                    let is_nested = self.0[..idx]
+                        .iter()
+                        .rev()
+                        .find(|i| !matches!(i, Instruction::FixedParse(..)))
+                        .is_some_and(Instruction::is_nested);
+                    let split = if is_nested {
+                        quote!(::prse::__private::split_once_nested(__prse_remaining, #l_string))
+                    } else {
+                        let l_string = string_to_tokens(l_string);
+                        quote!(__prse_remaining.split_once(#l_string))
+                    };
                     let l_string = string_to_tokens(l_string);
 
                     result.append_all(if cfg!(feature = "alloc") {
                         quote! {
-                            (__prse_parse, __prse_remaining) = __prse_remaining.split_once(#l_string)
+                            (__prse_parse, __prse_remaining) = #split
                                 .ok_or_else(|| ::prse::ParseError::Literal {expected: (#l_string).into(), found: __prse_remaining.into()})?;
                         }
                     } else {
                         quote! {
-                            (__prse_parse, __prse_remaining) = __prse_remaining.split_once(#l_string)
+                            (__prse_parse, __prse_remaining) = #split
                                 .ok_or_else(|| ::prse::ParseError::Literal)?;
                         }
                     });
 
                     if let Some(t) = store_token {
                         store_token = None;
+                        result.append_all(trailing_fixed.drain(..).rev());
                         result.append_all(t);
                     }
                 }
+                Instruction::FixedParse(_, width, trim) if store_token.is_some() => {
+                    // The width of the previous capture is unknown so take this one from the end.
+                    let args = width.to_args();
+                    let parse = parse(trimmed(quote!(__prse_fixed), *trim));
+                    trailing_fixed.push(quote! {
+                        let __prse_fixed;
+                        (__prse_parse, __prse_fixed) = ::prse::__private::rsplit_width(__prse_parse, #args)?;
+                        let #var = #parse?;
+                    });
+                }
+                Instruction::FixedParse(_, width, trim) => {
+                    let args = width.to_args();
+                    let parse = parse(trimmed(quote!(__prse_parse), *trim));
+                    result.append_all(quote! {
+                        (__prse_parse, __prse_remaining) = ::prse::__private::split_width(__prse_remaining, #args)?;
+                        let #var = #parse?;
+                    });
+                }
                 Instruction::Parse(_) => {
+                    let parse = parse(quote!(__prse_parse));
                     store_token = Some(quote! {
-                        let #var = ::prse::__private::try_parse_context(__prse_parse, __prse_input)?;
+                        let #var = #parse?;
                     });
                 }
                 Instruction::VecParse(..) => {
-                    let iter = i.gen_iter().unwrap();
+                    let iter = i.gen_iter(context).unwrap();
                     store_token = Some(quote! {
                         let #var = #iter.collect::<::core::result::Result<::#alloc_crate::vec::Vec<_>, ::prse::ParseError>>()?;
                     });
                 }
                 Instruction::IterParse(..) => {
-                    let iter = i.gen_iter().unwrap();
+                    let iter = i.gen_iter(context).unwrap();
                     store_token = Some(quote! {
                         let #var = #iter;
                     });
                 }
                 Instruction::MultiParse(_, _, count, _) => {
                     let idx = 0..*count;
-                    let iter = i.gen_iter().unwrap();
+                    let iter = i.gen_iter(context).unwrap();
                     store_token = Some(quote! {
                         let mut __prse_iter = #iter;
                         let #var = [ #(
@@ -290,7 +683,10 @@ impl Instructions {
                     return Err(::prse::ParseError::Literal)
                 }
             }
-        }, |t| quote! { __prse_parse = __prse_remaining; #t }));
+        }, |t| {
+            let trailing_fixed = trailing_fixed.iter().rev();
+            quote! { __prse_parse = __prse_remaining; #(#trailing_fixed)* #t }
+        }));
 
         let return_idents = self.0.iter().enumerate().filter_map(|(idx, i)| {
             i.get_var()?;
@@ -299,6 +695,43 @@ impl Instructions {
         result.append_all(quote! { Ok(( #(#return_idents),* )) });
     }
 
+    /// Generates the code writing these instructions to the formatter `__prse_f`,
+    /// where each capture is written from the next reference in `values`.
+    pub fn gen_display(&self, values: Vec<TokenStream>) -> TokenStream {
+        let mut values = values.into_iter();
+        let mut result = TokenStream::new();
+        for i in self.0.iter() {
+            result.append_all(match i {
+                Instruction::Lit(l) => quote!(__prse_f.write_str(#l)?;),
+                Instruction::Parse(_) => {
+                    let value = values.next().unwrap();
+                    quote!(::core::write!(__prse_f, "{}", #value)?;)
+                }
+                // Numbers are padded with zeros, so that they can be parsed back.
+                Instruction::FixedParse(_, width, _) => {
+                    let value = values.next().unwrap();
+                    let args = width.to_args();
+                    quote!(::prse::__private::write_fixed(__prse_f, &#value, #args)?;)
+                }
+                Instruction::VecParse(_, sep, _)
+                | Instruction::IterParse(_, sep, _)
+                | Instruction::MultiParse(_, sep, ..) => {
+                    let value = values.next().unwrap();
+                    let sep = sep.display_str();
+                    quote! {
+                        for (__prse_idx, __prse_item) in ::core::iter::IntoIterator::into_iter(#value).enumerate() {
+                            if __prse_idx != 0 {
+                                __prse_f.write_str(#sep)?;
+                            }
+                            ::core::write!(__prse_f, "{}", __prse_item)?;
+                        }
+                    }
+                }
+            });
+        }
+        result
+    }
+
     pub fn gen_return_idents(
         &self,
         return_idents: &mut Vec<Ident>,
@@ -324,6 +757,8 @@ impl Instructions {
                     func_idents.push(ident.clone());
                     renames.push((i.clone(), ident));
                 }
+                // Nested fields are assigned by the derive macro.
+                Var::Field(..) => func_idents.push(ident),
                 Var::Position(p) => {
                     func_idents.push(format_ident!("__prse_pos_{p}"));
                     return_idents.push(format_ident!("__prse_pos_{num_positions}"));
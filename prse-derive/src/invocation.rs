@@ -1,17 +1,21 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream, TokenTree};
 use quote::{ToTokens, TokenStreamExt};
 use syn::parse::{Parse, ParseStream};
 use syn::spanned::Spanned;
-use syn::{Expr, LitStr, Token};
+use syn::{Expr, Ident, LitStr, Path, Token};
 
+use crate::crate_path::{parse_crate_path, parse_trailing_crate_path, replace_crate_path};
 use crate::instructions::Instructions;
+use crate::var::Var;
 
 #[derive(Clone)]
 pub struct ParseInvocation {
     input: Expr,
     trailing: Option<TrailingExpr>,
+    context: Option<Expr>,
     instructions: Instructions,
     pub try_parse: bool,
+    crate_path: Option<Path>,
 }
 
 // Implementing our own enum so we do not need to enable syn's full feature
@@ -62,23 +66,207 @@ impl TrailingExpr {
 
 impl Parse for ParseInvocation {
     fn parse(stream: ParseStream) -> syn::Result<Self> {
+        Self::parse_args(stream, false)
+    }
+}
+
+impl ParseInvocation {
+    /// Parses the arguments of `parse_with!` and `try_parse_with!`, which take a context after the input.
+    pub fn parse_with(stream: ParseStream) -> syn::Result<Self> {
+        Self::parse_args(stream, true)
+    }
+
+    fn parse_args(stream: ParseStream, with_context: bool) -> syn::Result<Self> {
         let input = stream.parse()?;
         let trailing = TrailingExpr::parse(stream)?;
         let _coma: Token![,] = stream.parse()?;
+        let context = if with_context {
+            let context = stream.parse()?;
+            let _coma: Token![,] = stream.parse()?;
+            Some(context)
+        } else {
+            None
+        };
         let lit = stream.parse::<LitStr>()?;
         let lit_string = lit.value();
         let instructions = Instructions::new(&lit_string, lit.span())?;
+        instructions.validate_positions(lit.span())?;
+        if with_context {
+            instructions.validate_context(lit.span())?;
+        }
+        for i in instructions.0.iter() {
+            if let Some(Var::Field(ident, path)) = i.get_var() {
+                let path = path.iter().map(|p| format!(".{p}")).collect::<String>();
+                return Err(syn::Error::new(
+                    lit.span(),
+                    format!("Nested fields such as `{ident}{path}` can only be used in the derive macro."),
+                ));
+            }
+            if let Some(Var::Ident(ident)) = i.get_var() {
+                // Keywords are only allowed in the derive macro, where they can name a renamed field.
+                if syn::parse_str::<Ident>(&ident.to_string()).is_err() {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        format!("expected identifier, found keyword `{ident}`."),
+                    ));
+                }
+            }
+        }
+
+        let crate_path = parse_trailing_crate_path(stream)?;
 
         Ok(Self {
             input,
             trailing,
+            context,
             try_parse: false,
             instructions,
+            crate_path,
         })
     }
 }
 
-impl ParseInvocation {}
+#[derive(Clone)]
+pub struct UnparseInvocation {
+    value: Option<TokenStream>,
+    instructions: Instructions,
+    span: Span,
+    crate_path: Option<Path>,
+}
+
+impl Parse for UnparseInvocation {
+    fn parse(stream: ParseStream) -> syn::Result<Self> {
+        // The value is kept as tokens up to the last comma, so that we do not need syn's full feature.
+        let mut tokens = vec![];
+        while !stream.is_empty() {
+            tokens.push(stream.parse::<TokenTree>()?);
+        }
+        let crate_path = match tokens.as_slice() {
+            [.., TokenTree::Punct(coma), TokenTree::Ident(krate), TokenTree::Punct(eq), path]
+                if coma.as_char() == ',' && krate == "crate" && eq.as_char() == '=' =>
+            {
+                let path = parse_crate_path(&syn::parse2(path.to_token_stream())?)?;
+                tokens.truncate(tokens.len() - 4);
+                Some(path)
+            }
+            _ => None,
+        };
+        let lit: LitStr = match tokens.pop() {
+            Some(lit) => syn::parse2(lit.into_token_stream())?,
+            None => return Err(stream.error("Expected a pattern.")),
+        };
+        // The value can be left out when the pattern only contains named variables.
+        let value = match tokens.pop() {
+            None => None,
+            Some(TokenTree::Punct(p)) if p.as_char() == ',' && !tokens.is_empty() => {
+                Some(tokens.into_iter().collect())
+            }
+            Some(t) => {
+                return Err(syn::Error::new(
+                    t.span(),
+                    "Expected `,` before the pattern.",
+                ))
+            }
+        };
+        let instructions = Instructions::new(&lit.value(), lit.span())?;
+        instructions.validate_positions(lit.span())?;
+        let mut count = 0;
+        for i in instructions.0.iter() {
+            match i.get_var() {
+                Some(Var::Field(ident, path)) => {
+                    let path = path.iter().map(|p| format!(".{p}")).collect::<String>();
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        format!("Nested fields such as `{ident}{path}` can only be used in the derive macro."),
+                    ));
+                }
+                Some(Var::Ident(ident)) if syn::parse_str::<Ident>(&ident.to_string()).is_err() => {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        format!("expected identifier, found keyword `{ident}`."),
+                    ));
+                }
+                Some(Var::Implied | Var::Position(_)) => count += 1,
+                _ => {}
+            }
+        }
+        if count != 0 && value.is_none() {
+            return Err(syn::Error::new(
+                lit.span(),
+                "Expected a value to unparse before the pattern.",
+            ));
+        }
+
+        Ok(Self {
+            value,
+            instructions,
+            span: lit.span(),
+            crate_path,
+        })
+    }
+}
+
+impl ToTokens for UnparseInvocation {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        if !cfg!(feature = "alloc") {
+            tokens.append_all(
+                syn::Error::new(self.span, "The unparse macro requires the alloc feature.")
+                    .into_compile_error(),
+            );
+            return;
+        }
+        let count = self
+            .instructions
+            .0
+            .iter()
+            .filter(|i| matches!(i.get_var(), Some(Var::Implied | Var::Position(_))))
+            .count();
+        let mut implied = 0;
+        let values = self
+            .instructions
+            .0
+            .iter()
+            .filter_map(|i| {
+                let idx = match i.get_var()? {
+                    Var::Ident(ident) => return Some(quote!(&#ident)),
+                    Var::Position(p) => *p as usize,
+                    _ => {
+                        implied += 1;
+                        implied - 1
+                    }
+                };
+                // A single value is not wrapped in a tuple, as with the parse macro.
+                if count == 1 {
+                    Some(quote!(__prse_value))
+                } else {
+                    let idx = syn::Index::from(idx);
+                    Some(quote!(&__prse_value.#idx))
+                }
+            })
+            .collect();
+        let body = self.instructions.gen_display(values);
+        let value = self.value.as_ref().map(|value| {
+            quote_spanned! { value.span() =>
+                #[allow(unused_variables, clippy::needless_borrow)]
+                let __prse_value = &(#value);
+            }
+        });
+
+        let result = quote! {
+            {
+                #value
+                ::prse::__private::unparse(|__prse_f| {
+                    #body
+                    Ok(())
+                })
+            }
+        };
+        tokens.append_all(match &self.crate_path {
+            Some(path) => replace_crate_path(result, path),
+            None => result,
+        });
+    }
+}
 
 impl ToTokens for ParseInvocation {
     fn to_tokens(&self, tokens: &mut TokenStream) {
@@ -99,18 +287,31 @@ impl ToTokens for ParseInvocation {
             let mut __prse_remaining = __prse_input;
         };
 
-        self.instructions.gen_body(&mut body);
+        let context = self.context.is_some();
+        self.instructions.gen_body(&mut body, context);
 
-        let function = self.instructions.gen_function(body, func_name.clone());
+        let function = self
+            .instructions
+            .gen_function(body, func_name.clone(), context);
 
         let mut result = quote_spanned! { input.span() =>
             #[allow(clippy::needless_borrow)]
             let __prse_input: &str = &#input #trailing;
         };
 
+        let call = match &self.context {
+            Some(ctx) => {
+                result.append_all(quote_spanned! { ctx.span() =>
+                    let __prse_ctx: &mut _ = #ctx;
+                });
+                quote!(#func_name (__prse_input, __prse_input, __prse_ctx))
+            }
+            None => quote!(#func_name (__prse_input, __prse_input)),
+        };
+
         result.append_all(if self.try_parse {
             quote! {
-                match #func_name (__prse_input) {
+                match #call {
                     Ok(( #(#func_idents),* )) => {
                         #renames
                         Ok(( #(#return_idents),* ))
@@ -120,7 +321,7 @@ impl ToTokens for ParseInvocation {
             }
         } else {
             quote! {
-                let ( #(#func_idents),* ) = ::prse::__private::unwrap_parse(#func_name (__prse_input));
+                let ( #(#func_idents),* ) = ::prse::__private::unwrap_parse(#call);
                 #renames
                 #[allow(clippy::unused_unit)]
                 {
@@ -129,7 +330,7 @@ impl ToTokens for ParseInvocation {
             }
         });
 
-        tokens.append_all(quote! {
+        let result = quote! {
             {
                 use ::prse::Parse;
 
@@ -137,6 +338,10 @@ impl ToTokens for ParseInvocation {
 
                 #result
             }
+        };
+        tokens.append_all(match &self.crate_path {
+            Some(path) => replace_crate_path(result, path),
+            None => result,
         });
     }
 }
@@ -7,15 +7,21 @@ extern crate quote;
 #[macro_use]
 extern crate syn;
 
-use derive::Derive;
-use invocation::ParseInvocation;
+use derive::ParseDerive;
+use invocation::{ParseInvocation, UnparseInvocation};
 use proc_macro::TokenStream;
 use quote::ToTokens;
 
+mod attributes;
+mod crate_path;
 mod derive;
+mod dispatch;
 mod expand_derive;
+mod expand_display;
+mod expand_schema;
 mod instructions;
 mod invocation;
+mod shadow;
 mod var;
 
 /// The `parse` macro allows you to parse a string into any type that implements [`Parse`](trait.Parse.html).
@@ -95,12 +101,70 @@ mod var;
 /// ```ignore
 /// assert_eq!([1, 2, 3], parse!("1-2---3", "{:-:!3}"));
 /// ```
+///
+/// They also allow `{<var>:<sep>:#<kind>}` syntax to ignore any separators that are inside of `()`, `[]` or `{}`
+/// brackets, or inside of `"` quoted strings (which can be escaped using `\"` or `""`).
+/// The literal that comes straight after the repetition is also matched this way.
+/// ```ignore
+/// let [a, b, c]: [&str; 3] = parse!(r#"[1,[2,3]],"4,5",6"#, "{:,:#3}");
+/// assert_eq!((a, b, c), ("[1,[2,3]]", r#""4,5""#, "6"));
+/// ```
 /// ## Empty separators
 ///
 /// If the separator is an empty string slice (e.g. `{::}`) then the multi-parsers will iterate over every [char].
 /// ```ignore
 /// assert_eq!([3, 2, 1], parse!("321", "{::3}"))
 /// ```
+///
+/// ## Separator sets
+///
+/// A separator surrounded by square brackets (e.g. `{:[,;]:}`) will split on any of the characters inside it.
+/// If the brackets contain a `|` (e.g. `{:[, | and ]:}`) it will instead split on any of the strings
+/// between each `|`. A `\` can be used to escape a `|` or `]` inside the brackets, and a separator
+/// that should start with a `[` can be escaped with `\[`.
+/// ```ignore
+/// assert_eq!([1, 2, 3], parse!("1,2;3", "{:[,;]:3}"));
+/// assert_eq!([1, 2, 3], parse!("1, 2 and 3", "{:[, | and ]:3}"));
+/// ```
+///
+/// ## Whitespace separators
+///
+/// The `\s` separator (e.g. `{:\s:}`) splits on runs of whitespace, similar to [`str::split_whitespace`].
+/// ```ignore
+/// assert_eq!([1, 2, 3], parse!(" 1 \t2\n  3 ", "{:\\s:3}"));
+/// ```
+///
+/// ## Chunks
+///
+/// A `~<size>` separator (e.g. `{:~2:}`) will split the string into chunks of `size` chars instead.
+/// The string must be a multiple of the chunk size, otherwise a [`Width`](enum.ParseError.html#variant.Width) error is returned.
+/// When using the Iterator repetition, [`ParseChunks`](struct.ParseChunks.html) is returned.
+/// A separator that should start with a `~` can be escaped with `\~`.
+///
+/// ```ignore
+/// let input = "#ff8000";
+/// let rgb: [u8; 3] = parse!(input, "#{:~2:3}")
+///     .map(|hex: &str| u8::from_str_radix(hex, 16).unwrap());
+///
+/// assert_eq!(rgb, [255, 128, 0]);
+/// ```
+///
+/// # Fixed width
+///
+/// You can parse an exact number of characters using the `{<var>:w<width>}` syntax,
+/// or an exact number of bytes using the `{<var>:b<width>}` syntax.
+/// Unlike other captures, fixed width captures do not need a literal to separate them from other captures.
+/// The whole width is passed to the capture, including any spaces used to pad the value.
+/// Adding a `t` after the width, such as `{<var>:w<width>t}`, removes the trailing spaces before the capture is parsed.
+///
+/// ```ignore
+/// let input = "ACC0042  17.5Al   Bob";
+///
+/// let (account, id, amount, name, rest): (&str, u32, f32, &str, &str) = parse!(input, "{:w3}{:w4}{:w6}{:w5t}{}");
+///
+/// assert_eq!((account, id, amount, name, rest), ("ACC", 42, 17.5, "Al", "Bob"));
+/// ```
+///
 /// # Syntax
 ///
 /// The [`parse!`] macro uses a literal with `{}` brackets to denote where it should
@@ -154,6 +218,67 @@ pub fn try_parse(input: TokenStream) -> TokenStream {
     input.to_token_stream().into()
 }
 
+/// Parses like [`parse!`] while passing a context to each capture, which is parsed using [`ParseWith`](trait.ParseWith.html).
+///
+/// The context is given as a mutable reference after the input.
+/// The builtin types and the types deriving [`Parse`](trait.Parse.html) without a context ignore it.
+/// Iterator repetitions are parsed lazily so they cannot be used with a context.
+///
+/// ```ignore
+/// let mut interner = Interner::default();
+/// let (a, b, len): (Symbol, Vec<Symbol>, u32) = parse_with!("x: y,x 2", &mut interner, "{}: {:,:} {}");
+///
+/// assert_eq!(a, b[1]);
+/// ```
+#[proc_macro]
+pub fn parse_with(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input with ParseInvocation::parse_with);
+    input.to_token_stream().into()
+}
+
+/// Returns a [`Result`](https://doc.rust-lang.org/stable/std/result/enum.Result.html) instead of unwrapping like [`parse_with!`].
+///
+/// For more information please look at [`parse_with!`].
+/// ```ignore
+/// let mut interner = Interner::default();
+/// let symbol: Result<Symbol, _> = try_parse_with!("let x", &mut interner, "let {}");
+///
+/// assert!(symbol.is_ok());
+/// ```
+#[proc_macro]
+pub fn try_parse_with(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input with ParseInvocation::parse_with);
+    input.try_parse = true;
+    input.to_token_stream().into()
+}
+
+/// Writes values back into a [`String`](https://doc.rust-lang.org/stable/std/string/struct.String.html) using the same syntax as [`parse!`],
+/// so that the result can be parsed back into the same values.
+///
+/// The value to write is given before the pattern: a tuple when the pattern has multiple implied or positional variables,
+/// or a single value otherwise. Named variables are written from the variable with the same name,
+/// in which case the value can be left out.
+///
+/// Each capture is written using its [`Display`](core::fmt::Display) implementation, a repetition is written
+/// with its separator between each item and a fixed width capture is padded to its width, failing if the value does not fit.
+/// This macro requires the `alloc` feature.
+///
+/// ```ignore
+/// let name = "numbers";
+/// let line: String = unparse!(([3, 7], vec![1, 2, 3]), "{name}: {0:,:2} {1:-:}");
+///
+/// assert_eq!(line, "numbers: 3,7 1-2-3");
+///
+/// let (position, numbers): ([u32; 2], Vec<u32>) = parse!(line, "numbers: {:,:2} {:-:}");
+/// assert_eq!(position, [3, 7]);
+/// assert_eq!(numbers, [1, 2, 3]);
+/// ```
+#[proc_macro]
+pub fn unparse(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as UnparseInvocation);
+    input.to_token_stream().into()
+}
+
 /// Automatically implements the [`Parse`](trait.Parse.html) trait using one of two methods.
 ///
 /// You can define how each field should be parsed using the `prse` attribute.
@@ -174,6 +299,24 @@ pub fn try_parse(input: TokenStream) -> TokenStream {
 /// assert_eq!(pos.y, 2);
 ///```
 ///
+/// Multiple prse attributes can be used, in which case the first one to match is used.
+///
+///```ignore
+/// use prse::{parse, Parse};
+///
+/// #[derive(Debug, Parse)]
+/// #[prse = "({x}, {y})"]
+/// #[prse = "{x} {y}"]
+/// struct Position {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let pos: Position = parse!("This is a position: 1 2", "This is a position: {}");
+/// assert_eq!(pos.x, 1);
+/// assert_eq!(pos.y, 2);
+///```
+///
 /// This can also be done on enums.
 ///
 ///```ignore
@@ -201,6 +344,94 @@ pub fn try_parse(input: TokenStream) -> TokenStream {
 /// assert_eq!(pos2, Position::NoPos);
 /// assert_eq!(pos3, Position::NoPos);
 ///```
+///
+/// A prse attribute on the enum itself can be used to share a pattern between each variant.
+/// The `{}` in the enum's pattern is replaced by each variant's pattern, and any named
+/// variables are parsed into the field of the same name in each variant.
+///
+///```ignore
+/// use prse::{parse, Parse};
+///
+/// #[derive(Debug, Parse, Eq, PartialEq)]
+/// #[prse = "CMD {id}: {}"]
+/// enum Command {
+///     #[prse = "move {x} {y}"]
+///     Move { id: u32, x: i32, y: i32 },
+///     #[prse = "stop"]
+///     Stop { id: u32 },
+/// }
+///
+/// let command: Command = parse!("CMD 3: move 1 -2", "{}");
+/// assert_eq!(command, Command::Move { id: 3, x: 1, y: -2 });
+///```
+///
+/// Using `#[prse(rename_all = "...")]` on an enum, any variant without a prse attribute will be parsed
+/// using its name converted to `lowercase`, `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case`,
+/// `SCREAMING_SNAKE_CASE`, `kebab-case` or `SCREAMING-KEBAB-CASE`, followed by each of its fields.
+/// The fields are separated by a space, which can be changed using `#[prse(sep = "...")]`,
+/// and a variant's name can be changed using `#[prse(rename = "...")]`.
+///
+///```ignore
+/// use prse::{parse, Parse};
+///
+/// #[derive(Debug, Parse, Eq, PartialEq)]
+/// #[prse(rename_all = "snake_case", sep = ":")]
+/// enum Action {
+///     GoNorth,
+///     #[prse(rename = "wait")]
+///     Sleep(u32),
+///     Jump { height: u32 },
+/// }
+///
+/// assert_eq!(Action::GoNorth, parse!("go_north", "{}"));
+/// assert_eq!(Action::Sleep(5), parse!("wait:5", "{}"));
+/// assert_eq!(Action::Jump { height: 2 }, parse!("jump:2", "{}"));
+///```
+///
+/// A single variant can be marked with `#[prse(other)]` to be used when no other variant matches.
+/// It is always tried last, and if it does not have a prse attribute the whole input is parsed into its only field.
+///
+///```ignore
+/// use prse::{parse, Parse};
+///
+/// #[derive(Debug, Parse, Eq, PartialEq)]
+/// enum Response<'a> {
+///     #[prse = "OK"]
+///     Ok,
+///     #[prse(other)]
+///     Unknown(&'a str),
+/// }
+///
+/// assert_eq!(Response::Ok, parse!("OK", "{}"));
+/// assert_eq!(Response::Unknown("NOT OK"), parse!("NOT OK", "{}"));
+///```
+///
+/// Variants are tried in the order they are declared, so a warning is emitted for any
+/// pattern that is shadowed by an earlier one and can never be matched.
+/// Adding `#[prse(longest_match)]` to the enum instead tries every pattern and returns the variant
+/// whose pattern matched the most of the input, as any text before a leading literal is ignored.
+/// If several patterns match the same amount of the input, the one declared first is chosen,
+/// and the `other` variant is still only used when no other variant matches.
+///
+///```ignore
+/// use prse::{parse, Parse};
+///
+/// #[derive(Debug, Parse, Eq, PartialEq)]
+/// #[prse(longest_match)]
+/// enum Command<'a> {
+///     #[prse = "move {}"]
+///     Move(&'a str),
+///     #[prse = "{}"]
+///     Say(&'a str),
+/// }
+///
+/// assert_eq!(Command::Move("5"), parse!("move 5", "{}"));
+/// assert_eq!(Command::Say("I move 5"), parse!("I move 5", "{}"));
+///```
+///
+/// With the `alloc` feature, if no variant matches a [`Variants`](enum.ParseError.html#variant.Variants)
+/// error is returned containing the error of each variant,
+/// along with the closest unit variant if the input looks like a typo of one.
 /// If no prse attributes are found, it will use your [`FromStr`](core::str::FromStr) implementation.
 /// ```ignore
 /// use prse::{parse, Parse};
@@ -229,8 +460,378 @@ pub fn try_parse(input: TokenStream) -> TokenStream {
 /// assert_eq!(pos.x, 1);
 /// assert_eq!(pos.y, 2);
 /// ```
+///
+/// # Tagged enums
+///
+/// An enum can be given a `#[prse(tag = "...")]` attribute containing a named capture for the tag and a `{}` for the rest of the input.
+/// The tag is matched once and selects the variant whose `#[prse(tag_value = "...")]` is equal to it,
+/// which then parses the rest of the input using its own patterns. Without a `tag_value` attribute,
+/// a variant uses its renamed name. Without a pattern, a variant parses each of its fields separated by the separator,
+/// while a unit variant expects the rest of the input to be empty. When the tag and the rest of the input are only
+/// separated by a literal, such as in `{kind} {}`, a unit variant can also be written as only its tag.
+///
+/// ```ignore
+/// use prse::{parse, Parse};
+///
+/// #[derive(Debug, Parse, PartialEq)]
+/// #[prse(tag = "{kind} {}")]
+/// enum Reading {
+///     #[prse(tag_value = "temp")]
+///     Temperature(f32),
+///     #[prse(tag_value = "gps")]
+///     #[prse = "{lat},{lon}"]
+///     Position { lat: f32, lon: f32 },
+/// }
+///
+/// assert_eq!(Reading::Temperature(21.5), parse!("temp 21.5", "{}"));
+/// assert_eq!(Reading::Position { lat: 51.5, lon: -0.1 }, parse!("gps 51.5,-0.1", "{}"));
+/// ```
+///
+/// # Discriminants
+///
+/// An enum without any fields can be given a `#[prse(discriminant)]` attribute,
+/// which also parses each variant from its integer discriminant.
+/// Variants then do not need a prse attribute, and the discriminant is tried before any variant pattern.
+/// The [`Display`](derive.Display.html) derive writes variants without a pattern as their discriminant.
+///
+/// ```ignore
+/// use prse::{parse, Flags, Parse};
+///
+/// #[derive(Debug, Parse, Eq, PartialEq)]
+/// #[prse(discriminant, rename_all = "lowercase")]
+/// enum Permission {
+///     Read = 4,
+///     Write = 2,
+///     Execute = 1,
+/// }
+///
+/// assert_eq!(Permission::Write, parse!("2", "{}"));
+/// assert_eq!(Permission::Execute, parse!("execute", "{}"));
+///
+/// let Flags(permissions): Flags<Vec<Permission>> = parse!("read|1", "{}");
+/// assert_eq!(permissions, [Permission::Read, Permission::Execute]);
+/// ```
+///
+/// Bit flags such as `READ|WRITE` can be parsed using [`Flags`](struct.Flags.html).
+///
+/// # Field attributes
+///
+/// Fields can also be given a `#[prse(...)]` attribute with the following options:
+///
+/// - `with = path`: parses the captured string slice using the function at `path`,
+///   which must return a `Result` whose error can be converted into a [`ParseError`](enum.ParseError.html).
+/// - `default` or `default = expr`: uses [`Default`](core::default::Default) (or `expr`) when the field is not in the parse string.
+/// - `skip`: the field can never be parsed and is always created using [`Default`](core::default::Default).
+/// - `rename = "name"`: the name of the field in the parse string.
+/// - `validate = path`: checks the parsed field using the function at `path`, which takes a reference to the field
+///   and must return a `Result<(), E>` where `E` can be converted into a [`ParseError`](enum.ParseError.html).
+/// - `range = expr`: checks that the parsed field is contained in the range `expr`.
+///
+/// ```ignore
+/// use prse::{parse, Parse};
+///
+/// fn parse_hex(s: &str) -> Result<u32, std::num::ParseIntError> {
+///     u32::from_str_radix(s, 16)
+/// }
+///
+/// #[derive(Debug, Parse)]
+/// #[prse = "{type} #{colour}"]
+/// struct Shape<'a> {
+///     #[prse(rename = "type")]
+///     kind: &'a str,
+///     #[prse(with = parse_hex)]
+///     colour: u32,
+///     #[prse(default = 1.0)]
+///     scale: f32,
+///     #[prse(skip)]
+///     cached_area: Option<f32>,
+/// }
+///
+/// let shape: Shape = parse!("square #ff8000", "{}");
+/// assert_eq!(shape.kind, "square");
+/// assert_eq!(shape.colour, 0xff8000);
+/// assert_eq!(shape.scale, 1.0);
+/// assert_eq!(shape.cached_area, None);
+/// ```
+///
+/// Structs and enum variants can also be given a `#[prse(validate = path)]` attribute,
+/// which checks the whole value after it has been parsed.
+/// When a validation fails, a [`Validation`](enum.ParseError.html#variant.Validation) error is returned
+/// containing the name of the field, and a derived enum will try its next variant.
+///
+/// ```ignore
+/// use prse::{parse, Parse};
+///
+/// #[derive(Debug, Parse, Eq, PartialEq)]
+/// #[prse = "{start}..{end}"]
+/// #[prse(validate = Span::check)]
+/// struct Span {
+///     #[prse(range = ..1000)]
+///     start: u32,
+///     end: u32,
+/// }
+///
+/// impl Span {
+///     fn check(&self) -> Result<(), ()> {
+///         if self.start <= self.end { Ok(()) } else { Err(()) }
+///     }
+/// }
+///
+/// assert_eq!(Span { start: 1, end: 4 }, parse!("1..4", "{}"));
+/// let res: Result<Span, _> = prse::try_parse!("4..1", "{}");
+/// assert!(res.is_err());
+/// ```
+///
+/// # Nested fields
+///
+/// A pattern can capture into the fields of a field using a path such as `{pos.x}`.
+/// The outer field is created using its `default` attribute, or [`Default`](core::default::Default) otherwise,
+/// after which each captured nested field is parsed and assigned to it.
+/// A field cannot be captured both directly and through its nested fields,
+/// and nested fields cannot be used with the `with`, `validate` or `range` attributes.
+///
+/// ```ignore
+/// use prse::{parse, Parse};
+///
+/// #[derive(Debug, Default)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// #[derive(Debug, Parse)]
+/// #[prse = "{name} at ({pos.x}, {pos.y})"]
+/// struct Player<'a> {
+///     name: &'a str,
+///     pos: Point,
+/// }
+///
+/// let player: Player = parse!("ferris at (3, -4)", "{}");
+/// assert_eq!(player.pos.x, 3);
+/// assert_eq!(player.pos.y, -4);
+/// ```
+///
+/// # Token structs
+///
+/// A struct can be given a `#[prse(tokens)]` attribute instead of a pattern,
+/// which parses each of its fields in declaration order from the input split on runs of whitespace.
+/// A different separator can be used with `#[prse(tokens = ",")]`, in which case empty items are not skipped.
+/// A field with a `default` attribute uses it if there are not enough items.
+///
+/// ```ignore
+/// use prse::{parse, Parse};
+///
+/// #[derive(Debug, Parse)]
+/// #[prse(tokens)]
+/// struct Process<'a> {
+///     pid: u32,
+///     user: &'a str,
+///     cpu: f32,
+/// }
+///
+/// let process: Process = parse!("  42 root\t 0.5", "{}");
+/// assert_eq!(process.pid, 42);
+/// assert_eq!(process.user, "root");
+/// assert_eq!(process.cpu, 0.5);
+/// ```
+///
+/// # Key-value structs
+///
+/// A struct with named fields can be given a `#[prse(kv)]` attribute instead of a pattern,
+/// which parses a list of key-value pairs that can be in any order.
+/// Each key is matched to the field with the same name (or `rename`), and the following options can be added:
+///
+/// - `sep = "..."`: the separator between each pair, defaults to `" "`. Empty pairs are skipped and
+///   separators inside brackets and quotes are ignored.
+/// - `assign = "..."`: the separator between a key and its value, defaults to `"="`.
+/// - `deny_unknown_fields`: returns an error on any unknown key, which are otherwise ignored.
+///
+/// A missing key uses the field's `default` or `None` if the field is an `Option`,
+/// which parses its inner type. Otherwise, an error is returned.
+///
+/// ```ignore
+/// use prse::{parse, Parse};
+///
+/// #[derive(Debug, Parse)]
+/// #[prse(kv)]
+/// struct Request<'a> {
+///     method: &'a str,
+///     #[prse(rename = "status")]
+///     code: u16,
+///     duration: Option<f32>,
+/// }
+///
+/// let request: Request = parse!("status=200 method=GET user=admin", "{}");
+/// assert_eq!(request.method, "GET");
+/// assert_eq!(request.code, 200);
+/// assert_eq!(request.duration, None);
+/// ```
+///
+/// # FromStr
+///
+/// A type without any lifetimes can be given a `#[prse(from_str)]` attribute, which also implements
+/// [`FromStr`](core::str::FromStr) and [`TryFrom<&str>`](core::convert::TryFrom) using [`ParseError`](enum.ParseError.html) as the error.
+/// This allows the type to be used with [`str::parse`] and any other API that expects [`FromStr`](core::str::FromStr).
+///
+/// ```ignore
+/// use prse::Parse;
+///
+/// #[derive(Debug, Parse, Eq, PartialEq)]
+/// #[prse(from_str)]
+/// #[prse = "{width}x{height}"]
+/// struct Size {
+///     width: u32,
+///     height: u32,
+/// }
+///
+/// assert_eq!("3x4".parse(), Ok(Size { width: 3, height: 4 }));
+/// assert_eq!(Size::try_from("5x1"), Ok(Size { width: 5, height: 1 }));
+/// ```
+///
+/// # Bounds and crate path
+///
+/// By default, each type parameter must implement [`Parse<'__prse_a>`](trait.Parse.html), where `'__prse_a` is the
+/// lifetime of the input. These bounds can be replaced using `#[prse(bound = "...")]`, which takes the where clause
+/// predicates of the generated implementation and can be empty.
+///
+/// The generated code refers to the prse crate as `::prse`, a different path can be given using `#[prse(crate = "path")]`,
+/// which is useful when prse is re-exported by another crate. The [`parse!`] and [`try_parse!`] macros
+/// can also be given a different path by adding `crate = "path"` after the pattern.
+///
+/// ```ignore
+/// use prse::{parse, Parse};
+/// use std::marker::PhantomData;
+///
+/// struct Meters;
+///
+/// #[derive(Debug, Parse)]
+/// #[prse(bound = "", crate = "prse")]
+/// #[prse = "{value}"]
+/// struct Length<Unit> {
+///     value: f64,
+///     #[prse(default)]
+///     unit: PhantomData<Unit>,
+/// }
+///
+/// let length: Length<Meters> = parse!("2.5", "{}");
+/// assert_eq!(length.value, 2.5);
+/// let value: f64 = parse!("2.5", "{}", crate = "prse");
+/// assert_eq!(value, 2.5);
+/// ```
+///
+/// # Context
+///
+/// A type given a `#[prse(context = C)]` attribute implements [`ParseWith<'__prse_a, C>`](trait.ParseWith.html) instead of
+/// [`Parse`](trait.Parse.html), passing the context to each field and to each item of a Vec or Array repetition.
+/// Its type parameters must implement `ParseWith<'__prse_a, C>` and it can be parsed using [`parse_with!`].
+/// The context cannot be used with the `from_str` attribute, on types without a `prse` attribute or with Iterator repetitions.
+///
+/// Without the attribute, the derived type implements `ParseWith` for any context by ignoring it,
+/// so `ParseWith` cannot also be implemented by hand for it, as the two implementations would overlap.
+/// A type that needs to use a context should be given the context attribute, or implement both traits by hand.
+///
+/// ```ignore
+/// use prse::{parse_with, Parse, ParseError, ParseWith};
+///
+/// struct Scale(f64);
+///
+/// struct Length(f64);
+///
+/// impl<'a> ParseWith<'a, Scale> for Length {
+///     fn from_str_with(s: &'a str, ctx: &mut Scale) -> Result<Self, ParseError> {
+///         Ok(Length(s.trim().parse::<f64>()? * ctx.0))
+///     }
+/// }
+///
+/// #[derive(Parse)]
+/// #[prse(context = Scale)]
+/// #[prse = "{name}: {sides:, :}"]
+/// struct Polygon<'a> {
+///     name: &'a str,
+///     sides: Vec<Length>,
+/// }
+///
+/// let polygon: Polygon = parse_with!("square: 1, 1, 1, 1", &mut Scale(2.5), "{}");
+/// assert_eq!(polygon.sides[3].0, 2.5);
+/// ```
+///
+/// # Schema
+///
+/// The derive macro also adds two associated constants to the type, which describe the input it accepts.
+/// `PRSE_PATTERNS` contains each pattern in the order they are tried, and `PRSE_SCHEMA` is a [`Schema`](struct.Schema.html)
+/// listing the name, pattern, literal prefix and fields of each variant.
+///
+/// ```ignore
+/// use prse::Parse;
+///
+/// #[derive(Parse)]
+/// #[prse = "{name}: {age}"]
+/// #[prse = "{age} {name}"]
+/// struct Person<'a> {
+///     name: &'a str,
+///     age: u8,
+/// }
+///
+/// assert_eq!(Person::PRSE_PATTERNS, ["{name}: {age}", "{age} {name}"]);
+/// assert_eq!(Person::PRSE_SCHEMA.variants[1].fields[0].ty, "&'a str");
+/// ```
 #[proc_macro_derive(Parse, attributes(prse))]
 pub fn derive_parse(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as Derive);
+    let input = parse_macro_input!(input as ParseDerive);
     input.into_token_stream().into()
 }
+
+/// Implements [`Display`](core::fmt::Display) using the same `prse` attributes as the [`Parse`](derive.Parse.html) derive,
+/// so that a value can be written back into a string that parses into the same value.
+///
+/// Each capture is written using its [`Display`](core::fmt::Display) implementation and each repetition is written
+/// with its separator between each item. A separator set uses its first separator and whitespace is written as a single space.
+/// Fixed width captures are padded to their width, using zeros for numbers and trailing spaces otherwise,
+/// and an error is returned if a value is too long to fit. The `t` flag removes the trailing spaces again
+/// when the value is parsed.
+///
+/// A struct or variant with multiple patterns is written using the first pattern that is tried when parsing.
+/// Fields that are not in the pattern are not written, and optional fields of a [key-value](derive.Parse.html#key-value-structs)
+/// struct are only written when they are `Some`.
+///
+/// A field parsed using the `with` attribute must also have a `display_with = path` attribute, where `path` is a function
+/// with the signature `fn(&T, &mut Formatter) -> fmt::Result`. Iterator captures and unit variants with the `other`
+/// attribute cannot be displayed.
+///
+/// ```ignore
+/// use prse::{parse, Parse};
+///
+/// fn parse_hex(s: &str) -> Result<u32, std::num::ParseIntError> {
+///     u32::from_str_radix(s, 16)
+/// }
+///
+/// fn display_hex(colour: &u32, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+///     write!(f, "{colour:06x}")
+/// }
+///
+/// #[derive(Debug, Parse, prse::Display, Eq, PartialEq)]
+/// #[prse = "{name} #{colour}: {tags:,:}"]
+/// struct Layer {
+///     name: String,
+///     #[prse(with = parse_hex, display_with = display_hex)]
+///     colour: u32,
+///     tags: Vec<String>,
+/// }
+///
+/// let layer: Layer = parse!("background #ff8000: hidden,locked", "{}");
+/// assert_eq!(layer.to_string(), "background #ff8000: hidden,locked");
+/// assert_eq!(layer, parse!(layer.to_string(), "{}"));
+/// ```
+#[proc_macro_derive(Display, attributes(prse))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as ParseDerive);
+    let tokens = input
+        .derive
+        .into_display_tokens()
+        .unwrap_or_else(syn::Error::into_compile_error);
+    match &input.crate_path {
+        Some(path) => crate_path::replace_crate_path(tokens, path),
+        None => tokens,
+    }
+    .into()
+}
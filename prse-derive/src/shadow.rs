@@ -0,0 +1,164 @@
+use crate::attributes::Field;
+use crate::derive::Fields;
+use crate::instructions::Instruction;
+use crate::var::Var;
+use proc_macro2::{Ident, Span};
+use quote::ToTokens;
+use syn::Type;
+
+/// A part of a pattern with each capture resolved to the field it is parsed into.
+enum Part<'a> {
+    Lit(&'a str),
+    Capture(Instruction, &'a Field),
+    /// A capture into a nested field, which keeps its variable as it is part of the field's path.
+    Nested(&'a Instruction, &'a Field),
+}
+
+impl PartialEq for Part<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Part::Lit(l), Part::Lit(r)) => l == r,
+            (Part::Nested(l_instr, l_field), Part::Nested(r_instr, r_field)) => {
+                l_instr == r_instr
+                    && l_field.ty.to_token_stream().to_string()
+                        == r_field.ty.to_token_stream().to_string()
+            }
+            (Part::Capture(l_instr, l_field), Part::Capture(r_instr, r_field)) => {
+                fn tokens(t: &impl ToTokens) -> String {
+                    t.to_token_stream().to_string()
+                }
+                l_instr == r_instr
+                    && tokens(&l_field.ty) == tokens(&r_field.ty)
+                    && l_field.with.as_ref().map(tokens) == r_field.with.as_ref().map(tokens)
+                    && l_field.validate.as_ref().map(tokens)
+                        == r_field.validate.as_ref().map(tokens)
+                    && l_field.range.as_ref().map(tokens) == r_field.range.as_ref().map(tokens)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Part<'_> {
+    /// Whether this part will successfully parse any string.
+    fn matches_any(&self) -> bool {
+        let Part::Capture(Instruction::Parse(_), field) = self else {
+            return false;
+        };
+        let is_any_str = match &field.ty {
+            Type::Reference(r) => matches!(&*r.elem, Type::Path(p) if p.path.is_ident("str")),
+            Type::Path(p) => p.path.segments.last().is_some_and(|s| {
+                s.arguments.is_empty()
+                    && ["String", "OsString", "PathBuf"].contains(&&*s.ident.to_string())
+            }),
+            _ => false,
+        };
+        is_any_str && field.with.is_none() && field.validate.is_none() && field.range.is_none()
+    }
+}
+
+/// Splits the pattern of some fields into its parts, returns `None` if the fields match any input.
+fn parts(fields: &Fields) -> Option<Vec<Part<'_>>> {
+    let (instructions, fields, is_named) = match fields {
+        Fields::Named(instructions, fields, _) => (instructions, fields, true),
+        Fields::Unnamed(instructions, fields, _) => (instructions, fields, false),
+        Fields::Unit(s) if s.is_empty() => return Some(vec![]),
+        Fields::Unit(s) => return Some(vec![Part::Lit(s)]),
+        Fields::Any => return None,
+    };
+    let parsed_fields: Vec<_> = fields.iter().filter(|f| !f.skip).collect();
+    let mut count = 0;
+    let parts = instructions
+        .0
+        .iter()
+        .map(|i| {
+            let field = match i.get_var() {
+                None => {
+                    let Instruction::Lit(l) = i else {
+                        unreachable!()
+                    };
+                    return Part::Lit(l);
+                }
+                Some(Var::Field(ident, _)) => {
+                    let field = parsed_fields.iter().find(|f| f.has_name(ident)).unwrap();
+                    return Part::Nested(i, field);
+                }
+                Some(Var::Ident(ident)) if is_named => {
+                    parsed_fields.iter().find(|f| f.has_name(ident)).unwrap()
+                }
+                Some(Var::Position(p)) => parsed_fields[*p as usize],
+                Some(_) => {
+                    count += 1;
+                    parsed_fields[count - 1]
+                }
+            };
+            Part::Capture(i.without_var(), field)
+        })
+        .collect();
+    Some(parts)
+}
+
+/// Whether every input that is matched by the later pattern is also matched by the earlier pattern.
+///
+/// When the patterns are compared by length, the later pattern is still chosen over an earlier pattern
+/// that ignores more of the input before its leading literal.
+///
+/// This is only an approximation so it may miss some shadowed patterns but will never
+/// report a pattern that can still be matched.
+fn is_shadowed(
+    earlier: &Option<Vec<Part<'_>>>,
+    later: &Option<Vec<Part<'_>>>,
+    by_length: bool,
+) -> bool {
+    match (earlier.as_deref(), later.as_deref()) {
+        (None, _) => true,
+        (_, None) => false,
+        (Some(earlier), Some(later)) if earlier == later => true,
+        (Some([capture]), _) => capture.matches_any(),
+        // Any text before the first literal is ignored, so only the literal must be found.
+        (Some([Part::Lit(prefix), capture]), Some(later)) if !by_length => {
+            capture.matches_any()
+                && later
+                    .iter()
+                    .any(|p| matches!(p, Part::Lit(l) if l.contains(prefix)))
+        }
+        _ => false,
+    }
+}
+
+/// Finds the patterns that can never be matched as they are shadowed by an earlier pattern,
+/// returning a warning message for each of them.
+///
+/// With the `longest_match` attribute, `longest_match` is the number of patterns that are compared by length
+/// before the patterns of the catch-all variant are tried.
+pub(crate) fn shadowed_patterns(
+    patterns: &[(Ident, Fields, Span)],
+    longest_match: Option<usize>,
+) -> Vec<(Span, String)> {
+    let parts: Vec<_> = patterns.iter().map(|(_, f, _)| parts(f)).collect();
+    let mut warnings = vec![];
+    for (idx, (ident, _, span)) in patterns.iter().enumerate() {
+        // A validated pattern can still fail after matching, so it never shadows another pattern.
+        let shadowing = (0..idx)
+            .filter(|&earlier| {
+                !matches!(
+                    patterns[earlier].1,
+                    Fields::Named(.., Some(_)) | Fields::Unnamed(.., Some(_))
+                )
+            })
+            .find(|&earlier| {
+                let by_length = longest_match.is_some_and(|compared| idx < compared);
+                is_shadowed(&parts[earlier], &parts[idx], by_length)
+            });
+        if let Some(earlier) = shadowing {
+            let earlier = &patterns[earlier].0;
+            let message = if earlier == ident {
+                format!("This pattern of the `{ident}` variant is shadowed by one of its earlier patterns and will never be matched.")
+            } else {
+                format!("This pattern of the `{ident}` variant is shadowed by the `{earlier}` variant and will never be matched.")
+            };
+            warnings.push((*span, message));
+        }
+    }
+    warnings
+}
@@ -1,5 +1,6 @@
-use crate::instructions::Instruction;
+use crate::instructions::{Instruction, Modifiers, Repetition, Separator, Width};
 use proc_macro2::{Ident, Span};
+use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
 use syn::{parse_str, LitInt};
 
@@ -8,12 +9,19 @@ pub enum Var {
     Implied,
     Ident(Ident),
     Position(u8),
+    /// A path to a field of a nested struct, such as `pos.x`.
+    Field(Ident, Vec<Ident>),
 }
 
 impl Var {
     pub fn add_span(&mut self, span: Span) {
-        if let Var::Ident(i) = self {
-            i.set_span(span)
+        match self {
+            Var::Ident(i) => i.set_span(span),
+            Var::Field(i, path) => {
+                i.set_span(span);
+                path.iter_mut().for_each(|i| i.set_span(span));
+            }
+            Var::Implied | Var::Position(_) => {}
         }
     }
 }
@@ -34,11 +42,21 @@ impl Parse for Var {
                     Ok(Var::Position(pos))
                 }
                 Err(_) => {
-                    let res = input.parse::<Ident>().map(Var::Ident)?;
+                    // Keywords are allowed so that fields can be renamed to them.
+                    let ident = input.call(Ident::parse_any)?;
+                    let mut path = vec![];
+                    while input.peek(Token![.]) {
+                        input.parse::<Token![.]>()?;
+                        path.push(input.call(Ident::parse_any)?);
+                    }
                     if !input.is_empty() {
                         return Err(input.error("expected identifier"));
                     }
-                    Ok(res)
+                    if path.is_empty() {
+                        Ok(Var::Ident(ident))
+                    } else {
+                        Ok(Var::Field(ident, path))
+                    }
                 }
             }
         }
@@ -51,17 +69,39 @@ pub fn parse_var(input: String, input_span: Span) -> syn::Result<Instruction> {
             let mut var: Var = parse_str(var)?;
             var.add_span(input_span);
             if let Some((sep, num)) = split.rsplit_once(':') {
-                let (num, is_multi_sep) = num
-                    .strip_prefix('!')
-                    .map(|num| (num, true))
-                    .unwrap_or((num, false));
+                let mut modifiers = Modifiers::default();
+                let num = num.trim_start_matches(|c| match c {
+                    '!' => {
+                        modifiers.is_multi = true;
+                        true
+                    }
+                    '#' => {
+                        modifiers.is_nested = true;
+                        true
+                    }
+                    _ => false,
+                });
 
-                if sep.is_empty() && is_multi_sep {
+                let sep = parse_separator(sep, input_span)?;
+
+                if matches!(sep, Repetition::Chunk(_)) && modifiers != Modifiers::default() {
+                    return Err(syn::Error::new(
+                        input_span,
+                        "modifiers are not supported with chunk iterators.",
+                    ));
+                }
+                if sep.is_empty() && modifiers.is_multi {
                     return Err(syn::Error::new(
                         input_span,
                         "skipping separators is not supported with char iterators.",
                     ));
                 }
+                if sep.is_empty() && modifiers.is_nested {
+                    return Err(syn::Error::new(
+                        input_span,
+                        "nested separators are not supported with char iterators.",
+                    ));
+                }
 
                 Ok(if num.trim().is_empty() {
                     if !cfg!(feature = "alloc") {
@@ -70,11 +110,11 @@ pub fn parse_var(input: String, input_span: Span) -> syn::Result<Instruction> {
                             "alloc feature is required to parse into a Vec.",
                         ));
                     }
-                    Instruction::VecParse(var, String::from(sep), is_multi_sep)
+                    Instruction::VecParse(var, sep, modifiers)
                 } else {
                     match num.parse() {
-                        Ok(0_u8) => Instruction::IterParse(var, String::from(sep), is_multi_sep),
-                        Ok(x) => Instruction::MultiParse(var, String::from(sep), x, is_multi_sep),
+                        Ok(0_u8) => Instruction::IterParse(var, sep, modifiers),
+                        Ok(x) => Instruction::MultiParse(var, sep, x, modifiers),
                         Err(_) => {
                             return Err(syn::Error::new(
                                 input_span,
@@ -83,6 +123,8 @@ pub fn parse_var(input: String, input_span: Span) -> syn::Result<Instruction> {
                         }
                     }
                 })
+            } else if let Some((width, trim)) = parse_width(split.trim(), input_span)? {
+                Ok(Instruction::FixedParse(var, width, trim))
             } else {
                 Err(syn::Error::new(
                     input_span,
@@ -98,6 +140,87 @@ pub fn parse_var(input: String, input_span: Span) -> syn::Result<Instruction> {
     }
 }
 
+/// Parses a fixed width such as `w8` or `b8`, along with whether a trailing `t` asks for the padding to be trimmed.
+fn parse_width(width: &str, input_span: Span) -> syn::Result<Option<(Width, bool)>> {
+    let (width, is_chars) = match (width.strip_prefix('w'), width.strip_prefix('b')) {
+        (Some(w), _) => (w, true),
+        (_, Some(w)) => (w, false),
+        _ => return Ok(None),
+    };
+    let (width, trim) = match width.strip_suffix('t') {
+        Some(w) => (w, true),
+        None => (width, false),
+    };
+    match width.parse() {
+        Ok(0_usize) | Err(_) => Err(syn::Error::new(
+            input_span,
+            format!("expected a width greater than 0 but found {width}."),
+        )),
+        Ok(w) if is_chars => Ok(Some((Width::Chars(w), trim))),
+        Ok(w) => Ok(Some((Width::Bytes(w), trim))),
+    }
+}
+
+fn parse_separator(sep: &str, input_span: Span) -> syn::Result<Repetition> {
+    if sep == "\\s" {
+        return Ok(Separator::Whitespace.into());
+    }
+    if let Some(sep) = sep.strip_prefix('\\') {
+        if sep.starts_with(['[', '~', '\\']) {
+            return Ok(sep.into());
+        }
+    }
+    if let Some(size) = sep.strip_prefix('~') {
+        match size.parse() {
+            Ok(0_usize) => {
+                return Err(syn::Error::new(
+                    input_span,
+                    "expected a chunk size greater than 0.",
+                ))
+            }
+            Ok(size) => return Ok(Repetition::Chunk(size)),
+            Err(_) => {}
+        }
+    }
+    let set = match sep.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        Some(set) => set,
+        None => return Ok(sep.into()),
+    };
+
+    let mut alternatives = vec![String::new()];
+    let mut has_bar = false;
+    let mut chars = set.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) => alternatives.last_mut().unwrap().push(c),
+                None => {
+                    return Err(syn::Error::new(
+                        input_span,
+                        "expected a character to escape in the separator set.",
+                    ))
+                }
+            },
+            '|' => {
+                has_bar = true;
+                alternatives.push(String::new());
+            }
+            c => alternatives.last_mut().unwrap().push(c),
+        }
+    }
+
+    if !has_bar {
+        alternatives = alternatives[0].chars().map(String::from).collect();
+    }
+    if alternatives.is_empty() || alternatives.iter().any(|a| a.is_empty()) {
+        return Err(syn::Error::new(
+            input_span,
+            "a separator set cannot contain an empty separator.",
+        ));
+    }
+    Ok(Separator::AnyOf(alternatives).into())
+}
+
 #[cfg(test)]
 mod tests {
     use proc_macro2::Span;
@@ -107,6 +230,20 @@ mod tests {
     #[test]
     fn test_instruction_pass() {
         use crate::instructions::Instruction::*;
+        use crate::instructions::Separator::*;
+        use crate::instructions::{Modifiers, Repetition::Chunk, Width};
+        const NONE: Modifiers = Modifiers {
+            is_multi: false,
+            is_nested: false,
+        };
+        const MULTI: Modifiers = Modifiers {
+            is_multi: true,
+            is_nested: false,
+        };
+        const NESTED: Modifiers = Modifiers {
+            is_multi: false,
+            is_nested: true,
+        };
         use crate::var::Var::*;
         #[rustfmt::skip]
         let cases = [
@@ -117,26 +254,46 @@ mod tests {
             ("{}{{{}}}{}", vec![Parse(Implied), Lit("{".into()), Parse(Implied), Lit("}".into()), Parse(Implied)]),
             (" {}{{:}}}}{} ", vec![Lit(" ".into()), Parse(Implied), Lit("{:}}".into()), Parse(Implied), Lit(" ".into())]),
             (" {} {}}}{}", vec![Lit(" ".into()), Parse(Implied), Lit(" ".into()), Parse(Implied), Lit("}".into()), Parse(Implied)]),
-            ("{:}}:}", vec![VecParse(Implied, "}".into(), false)]),
-            ("{:{{}}:}", vec![VecParse(Implied, "{}".into(), false)]),
-            ("{:{{}}: }", vec![VecParse(Implied, "{}".into(), false)]),
+            ("{:}}:}", vec![VecParse(Implied, "}".into(), NONE)]),
+            ("{:{{}}:}", vec![VecParse(Implied, "{}".into(), NONE)]),
+            ("{:{{}}: }", vec![VecParse(Implied, "{}".into(), NONE)]),
             ("{hello}", vec![Parse(Ident(syn::Ident::new("hello", Span::call_site())))]),
-            ("{:,:5}", vec![MultiParse(Implied, ",".into(), 5, false)]),
-            ("{:,:0}", vec![IterParse(Implied, ",".into(), false)]),
-            ("{:,:}", vec![VecParse(Implied, ",".into(), false)]),
-            ("{:,::1}", vec![MultiParse(Implied, ",:".into(), 1, false)]),
-            ("{:,::0}", vec![IterParse(Implied, ",:".into(), false)]),
-            ("{:,::}", vec![VecParse(Implied, ",:".into(), false)]),
-            ("{::,::85}", vec![MultiParse(Implied, ":,:".into(), 85, false)]),
-            ("{::,::0}", vec![IterParse(Implied, ":,:".into(), false)]),
-            ("{::,::}", vec![VecParse(Implied, ":,:".into(), false)]),
-            ("{::}", vec![VecParse(Implied, "".into(), false)]),
+            ("{:,:5}", vec![MultiParse(Implied, ",".into(), 5, NONE)]),
+            ("{:,:0}", vec![IterParse(Implied, ",".into(), NONE)]),
+            ("{:,:}", vec![VecParse(Implied, ",".into(), NONE)]),
+            ("{:,::1}", vec![MultiParse(Implied, ",:".into(), 1, NONE)]),
+            ("{:,::0}", vec![IterParse(Implied, ",:".into(), NONE)]),
+            ("{:,::}", vec![VecParse(Implied, ",:".into(), NONE)]),
+            ("{::,::85}", vec![MultiParse(Implied, ":,:".into(), 85, NONE)]),
+            ("{::,::0}", vec![IterParse(Implied, ":,:".into(), NONE)]),
+            ("{::,::}", vec![VecParse(Implied, ":,:".into(), NONE)]),
+            ("{::}", vec![VecParse(Implied, "".into(), NONE)]),
             ("{ 0  }", vec![Parse(Position(0))]),
             ("{1} {0}", vec![Parse(Position(1)), Lit(" ".into()), Parse(Position(0))]),
             ("{0} {  hiya }", vec![Parse(Position(0)), Lit(" ".into()), Parse(Ident(syn::Ident::new("hiya", Span::call_site())))]),
-            ("{:-:!}", vec![VecParse(Implied, "-".into(), true)]),
-            ("{:!:!0}", vec![IterParse(Implied, "!".into(), true)]),
-            ("{:!:!2}", vec![MultiParse(Implied, "!".into(), 2, true)]),
+            ("{:-:!}", vec![VecParse(Implied, "-".into(), MULTI)]),
+            ("{:!:!0}", vec![IterParse(Implied, "!".into(), MULTI)]),
+            ("{:!:!2}", vec![MultiParse(Implied, "!".into(), 2, MULTI)]),
+            ("{:[,;]:}", vec![VecParse(Implied, AnyOf(vec![",".into(), ";".into()]).into(), NONE)]),
+            ("{:[, | and ]:0}", vec![IterParse(Implied, AnyOf(vec![", ".into(), " and ".into()]).into(), NONE)]),
+            ("{:[\\||\\]]:2}", vec![MultiParse(Implied, AnyOf(vec!["|".into(), "]".into()]).into(), 2, NONE)]),
+            ("{:\\s:}", vec![VecParse(Implied, Whitespace.into(), NONE)]),
+            ("{:\\[,]:}", vec![VecParse(Implied, "[,]".into(), NONE)]),
+            ("{:\\\\s:}", vec![VecParse(Implied, "\\s".into(), NONE)]),
+            ("{:,:#}", vec![VecParse(Implied, ",".into(), NESTED)]),
+            ("{:~2:}", vec![VecParse(Implied, Chunk(2), NONE)]),
+            ("{:~13:0}", vec![IterParse(Implied, Chunk(13), NONE)]),
+            ("{:~:0}", vec![IterParse(Implied, "~".into(), NONE)]),
+            ("{:\\~3:0}", vec![IterParse(Implied, "~3".into(), NONE)]),
+            ("{:w3}", vec![FixedParse(Implied, Width::Chars(3), false)]),
+            ("{x: b12 }", vec![FixedParse(Ident(syn::Ident::new("x", Span::call_site())), Width::Bytes(12), false)]),
+            ("{:w4t}", vec![FixedParse(Implied, Width::Chars(4), true)]),
+            ("{x:b8t}", vec![FixedParse(Ident(syn::Ident::new("x", Span::call_site())), Width::Bytes(8), true)]),
+            ("{:w2}{}", vec![FixedParse(Implied, Width::Chars(2), false), Parse(Implied)]),
+            ("{}{:w2}{:b1}", vec![Parse(Implied), FixedParse(Implied, Width::Chars(2), false), FixedParse(Implied, Width::Bytes(1), false)]),
+            ("{:w2}{:,:}-{}{:b2}", vec![FixedParse(Implied, Width::Chars(2), false), VecParse(Implied, ",".into(), NONE), Lit("-".into()), Parse(Implied), FixedParse(Implied, Width::Bytes(2), false)]),
+            ("{:,:#!0}", vec![IterParse(Implied, ",".into(), Modifiers { is_multi: true, is_nested: true })]),
+            ("{:,:!#3}", vec![MultiParse(Implied, ",".into(), 3, Modifiers { is_multi: true, is_nested: true })]),
         ];
         for (input, expected) in cases {
             let output = Instructions::new(input, Span::call_site());
@@ -0,0 +1,99 @@
+use crate::{__private, Parse, ParseError, ParseWith};
+use core::fmt;
+
+/// A set of flags separated by `SEP`, such as `"READ|WRITE"`, which are parsed and collected into `C`.
+///
+/// Each flag is parsed into the item type of `C` after trimming its whitespace,
+/// and an empty string is parsed into an empty collection.
+/// This allows flags to be collected into a [`Vec`](https://doc.rust-lang.org/stable/std/vec/struct.Vec.html)
+/// or into any bit flags type that can be collected from and iterated over its own single flags.
+///
+/// ```
+/// # use prse::{parse, Flags, Parse};
+/// #[derive(Parse, Debug, PartialEq)]
+/// #[prse(rename_all = "UPPERCASE")]
+/// enum Permission {
+///     Read,
+///     Write,
+///     Execute,
+/// }
+///
+/// let Flags(permissions): Flags<Vec<Permission>> = parse!("mode: READ | WRITE", "mode: {}");
+/// assert_eq!(permissions, [Permission::Read, Permission::Write]);
+///
+/// let Flags(numbers): Flags<Vec<u32>, ','> = parse!("1,2,3", "{}");
+/// assert_eq!(numbers, [1, 2, 3]);
+/// ```
+///
+/// The flags can also be written back with the same separator using [`Display`](fmt::Display).
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Flags<C, const SEP: char = '|'>(pub C);
+
+impl<C, const SEP: char> Flags<C, SEP> {
+    /// Returns the collection of flags.
+    pub fn into_inner(self) -> C {
+        self.0
+    }
+}
+
+impl<'a, C, const SEP: char> Parse<'a> for Flags<C, SEP>
+where
+    C: IntoIterator + FromIterator<C::Item>,
+    C::Item: Parse<'a>,
+{
+    fn from_str(s: &'a str) -> Result<Self, ParseError> {
+        Self::from_str_in(s, s)
+    }
+
+    fn from_str_in(s: &'a str, input: &'a str) -> Result<Self, ParseError> {
+        if s.trim().is_empty() {
+            return Ok(Flags(core::iter::empty().collect()));
+        }
+        s.split(SEP)
+            .map(|flag| {
+                let item = <C::Item>::from_str_in(flag.trim(), input);
+                __private::add_err_multi_context(item, s, flag)
+            })
+            .collect::<Result<C, ParseError>>()
+            .map(Flags)
+    }
+}
+
+impl<'a, Ctx: ?Sized, C, const SEP: char> ParseWith<'a, Ctx> for Flags<C, SEP>
+where
+    C: IntoIterator + FromIterator<C::Item>,
+    C::Item: ParseWith<'a, Ctx>,
+{
+    fn from_str_with(s: &'a str, ctx: &mut Ctx) -> Result<Self, ParseError> {
+        Self::from_str_with_in(s, s, ctx)
+    }
+
+    fn from_str_with_in(s: &'a str, input: &'a str, ctx: &mut Ctx) -> Result<Self, ParseError> {
+        if s.trim().is_empty() {
+            return Ok(Flags(core::iter::empty().collect()));
+        }
+        s.split(SEP)
+            .map(|flag| {
+                let item = <C::Item>::from_str_with_in(flag.trim(), input, ctx);
+                __private::add_err_multi_context(item, s, flag)
+            })
+            .collect::<Result<C, ParseError>>()
+            .map(Flags)
+    }
+}
+
+impl<C, const SEP: char> fmt::Display for Flags<C, SEP>
+where
+    for<'b> &'b C: IntoIterator,
+    for<'b> <&'b C as IntoIterator>::Item: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, flag) in (&self.0).into_iter().enumerate() {
+            if idx != 0 {
+                write!(f, "{SEP}")?;
+            }
+            write!(f, "{flag}")?;
+        }
+        Ok(())
+    }
+}
@@ -47,6 +47,54 @@ pub trait Parse<'a> {
     fn from_str(s: &'a str) -> Result<Self, ParseError>
     where
         Self: Sized;
+
+    /// Parses a string `s` which is a part of the original `input`.
+    ///
+    /// This allows [`Spanned`](crate::Spanned) to record the position of `s` within `input`.
+    /// The default implementation ignores `input` and calls [`Parse::from_str`], so a hand-written
+    /// implementation which contains a `Spanned` value should forward `input` to it.
+    ///
+    /// ```
+    /// # use prse::{parse, Parse, ParseError, Spanned};
+    /// struct Quoted(Spanned<u32>);
+    ///
+    /// impl<'a> Parse<'a> for Quoted {
+    ///     fn from_str(s: &'a str) -> Result<Self, ParseError> {
+    ///         Self::from_str_in(s, s)
+    ///     }
+    ///
+    ///     fn from_str_in(s: &'a str, input: &'a str) -> Result<Self, ParseError> {
+    ///         Spanned::from_str_in(s.trim_matches('"'), input).map(Quoted)
+    ///     }
+    /// }
+    ///
+    /// let q: Quoted = parse!("id: \"42\"", "id: {}");
+    /// assert_eq!(q.0.span, 5..7);
+    /// ```
+    fn from_str_in(s: &'a str, input: &'a str) -> Result<Self, ParseError>
+    where
+        Self: Sized,
+    {
+        let _ = input;
+        Self::from_str(s)
+    }
+}
+
+/// Implements [`ParseWith`](crate::ParseWith) for any context by ignoring it.
+macro_rules! impl_parse_with {
+    ( $( $Ty: ty )+) => {
+        $(
+            impl<'a, C: ?Sized> $crate::ParseWith<'a, C> for $Ty {
+                fn from_str_with(s: &'a str, _ctx: &mut C) -> Result<Self, ParseError> {
+                    <Self as $crate::Parse<'a>>::from_str(s)
+                }
+
+                fn from_str_with_in(s: &'a str, input: &'a str, _ctx: &mut C) -> Result<Self, ParseError> {
+                    <Self as $crate::Parse<'a>>::from_str_in(s, input)
+                }
+            }
+        )+
+    };
 }
 
 impl<'a> Parse<'a> for &'a str {
@@ -55,6 +103,8 @@ impl<'a> Parse<'a> for &'a str {
     }
 }
 
+impl_parse_with!(&'a str);
+
 macro_rules! impl_parse {
     ( $( $Ty: ty )+) => {
         $(
@@ -63,6 +113,8 @@ macro_rules! impl_parse {
                     <Self as FromStr>::from_str(s.trim()).map_err(|e| e.into())
                 }
             }
+
+            impl_parse_with!($Ty);
         )+
     };
 }
@@ -76,6 +128,8 @@ macro_rules! impl_parse_infallible {
                     Ok(<Self as FromStr>::from_str(&s).unwrap())
                 }
             }
+
+            impl_parse_with!($Ty);
         )+
     };
 }
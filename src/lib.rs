@@ -133,6 +133,38 @@
 //! assert_eq!([1, 2, 3], parse!("1-2---3", "{:-:!3}"));
 //! ```
 //!
+//! They also allow `{<var>:<sep>:#<kind>}` syntax to ignore any separators that are inside of `()`, `[]` or `{}`
+//! brackets, or inside of `"` quoted strings (which can be escaped using `\"` or `""`).
+//! The literal that comes straight after the repetition is also matched this way.
+//! ```
+//! # use prse::parse;
+//! #
+//! let [a, b, c]: [&str; 3] = parse!(r#"[1,[2,3]],"4,5",6"#, "{:,:#3}");
+//! assert_eq!((a, b, c), ("[1,[2,3]]", r#""4,5""#, "6"));
+//! ```
+//!
+//! This allows you to parse recursive types.
+//! ```
+//! # use prse::{parse, Parse};
+//! #
+//! # #[cfg(feature = "alloc")] {
+//! #[derive(Parse, PartialEq, Debug)]
+//! enum Value {
+//!     #[prse = "[{:,:#}]"]
+//!     List(Vec<Value>),
+//!     #[prse = "{}"]
+//!     Int(i64),
+//! }
+//!
+//! let value: Value = parse!("[1,[2,3],[]]", "{}");
+//! assert_eq!(value, Value::List(vec![
+//!     Value::Int(1),
+//!     Value::List(vec![Value::Int(2), Value::Int(3)]),
+//!     Value::List(vec![]),
+//! ]));
+//! # }
+//! ```
+//!
 //! ## Empty separators
 //!
 //! If the separator is an empty string slice (e.g. `{::}`) then the multi-parsers will iterate over every [char] in the string.
@@ -142,16 +174,111 @@
 //! assert_eq!([3, 2, 1], parse!("321", "{::3}"))
 //! ```
 //!
+//! ## Separator sets
+//!
+//! A separator surrounded by square brackets (e.g. `{:[,;]:}`) will split on any of the characters inside it.
+//! If the brackets contain a `|` (e.g. `{:[, | and ]:}`) it will instead split on any of the strings
+//! between each `|`. A `\` can be used to escape a `|` or `]` inside the brackets, and a separator
+//! that should start with a `[` can be escaped with `\[`.
+//!
+//! ```
+//! # use prse::parse;
+//! #
+//! assert_eq!([1, 2, 3], parse!("1,2;3", "{:[,;]:3}"));
+//! assert_eq!([1, 2, 3], parse!("1, 2 and 3", "{:[, | and ]:3}"));
+//! ```
+//!
+//! ## Whitespace separators
+//!
+//! The `\s` separator (e.g. `{:\s:}`) splits on runs of whitespace, similar to [`str::split_whitespace`].
+//!
+//! ```
+//! # use prse::parse;
+//! #
+//! assert_eq!([1, 2, 3], parse!(" 1 \t2\n  3 ", "{:\\s:3}"));
+//! ```
+//!
+//! ## Chunks
+//!
+//! A `~<size>` separator (e.g. `{:~2:}`) will split the string into chunks of `size` chars instead.
+//! The string must be a multiple of the chunk size, otherwise a [`Width`](ParseError::Width) error is returned.
+//! When using the Iterator repetition, [`ParseChunks`](struct.ParseChunks.html) is returned.
+//! A separator that should start with a `~` can be escaped with `\~`.
+//!
+//! ```
+//!# use prse::parse;
+//!#
+//! let input = "#ff8000";
+//! let rgb: [u8; 3] = parse!(input, "#{:~2:3}")
+//!     .map(|hex: &str| u8::from_str_radix(hex, 16).unwrap());
+//!
+//! assert_eq!(rgb, [255, 128, 0]);
+//! ```
+//!
+//! # Fixed width
+//!
+//! You can parse an exact number of characters using the `{<var>:w<width>}` syntax,
+//! or an exact number of bytes using the `{<var>:b<width>}` syntax.
+//! Unlike other captures, fixed width captures do not need a literal to separate them from other captures.
+//! The whole width is passed to the capture, including any spaces used to pad the value.
+//! Adding a `t` after the width, such as `{<var>:w<width>t}`, removes the trailing spaces before the capture is parsed.
+//!
+//! ```
+//!# use prse::parse;
+//!#
+//! let input = "ACC0042  17.5Al   Bob";
+//!
+//! let (account, id, amount, name, rest): (&str, u32, f32, &str, &str) = parse!(input, "{:w3}{:w4}{:w6}{:w5t}{}");
+//!
+//! assert_eq!((account, id, amount, name, rest), ("ACC", 42, 17.5, "Al", "Bob"));
+//! ```
+//!
+//! # Writing values back
+//!
+//! The [`unparse!`] macro and the [`Display`](prse_derive::Display) derive macro write values
+//! back into a string using the same syntax, so that the string can be parsed back into the same values.
+//!
+//! ```
+//!# use prse::{parse, unparse, Parse};
+//!#
+//!# #[cfg(feature = "alloc")] {
+//! #[derive(Parse, prse::Display, PartialEq, Eq, Debug)]
+//! #[prse = "({x}, {y})"]
+//! struct Position {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! let position = Position { x: 1, y: -3 };
+//! assert_eq!(position.to_string(), "(1, -3)");
+//!
+//! let line = unparse!((position, vec![4, 2]), "{} moved by {:,:}");
+//! assert_eq!(line, "(1, -3) moved by 4,2");
+//!
+//! let (position, moves): (Position, Vec<i32>) = parse!(line, "{} moved by {:,:}");
+//! assert_eq!(position, Position { x: 1, y: -3 });
+//! assert_eq!(moves, [4, 2]);
+//!# }
+//! ```
+//!
 
-pub use prse_derive::{parse, try_parse, Parse};
+pub use prse_derive::{parse, parse_with, try_parse, try_parse_with, unparse, Display, Parse};
 
+pub use crate::flags::Flags;
 #[rustfmt::skip]
 pub use crate::lending_parse::{ExtParseStr, Parse};
 pub use crate::parse_error::ParseError;
 #[doc(hidden)]
 pub use crate::parse_error::__private;
-pub use crate::parse_iterators::{ParseChars, ParseIter};
+pub use crate::parse_iterators::{ParseChars, ParseChunks, ParseIter, Separator, WithOffsets};
+pub use crate::parse_with::ParseWith;
+pub use crate::schema::{Schema, SchemaField, SchemaVariant};
+pub use crate::spanned::Spanned;
 
+mod flags;
 mod lending_parse;
 mod parse_error;
 mod parse_iterators;
+mod parse_with;
+mod schema;
+mod spanned;
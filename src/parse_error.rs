@@ -10,6 +10,8 @@ use alloc::boxed::Box;
 use alloc::string::String;
 #[cfg(feature = "alloc")]
 use alloc::string::ToString;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
 #[cfg(feature = "std")]
 use std::error;
@@ -57,6 +59,13 @@ pub enum ParseError {
         /// The size of the array it found.
         found: u8,
     },
+    /// The variant returned when a fixed width capture finds more or less characters than what was expected.
+    Width {
+        /// The width it was expecting.
+        expected: usize,
+        /// The width it found.
+        found: usize,
+    },
     /// A variant that can be used when you need to return a simple error.
     /// When not using the `alloc` feature, `Other` is a unit variant.
     #[cfg(feature = "alloc")]
@@ -88,6 +97,33 @@ pub enum ParseError {
         /// The wrapped error
         error: Box<ParseError>,
     },
+    /// The variant returned when a derived enum could not parse any of its variants.
+    /// This variant is only enabled with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    Variants {
+        /// The name of each variant that was tried along with the error it returned.
+        errors: Vec<(&'static str, ParseError)>,
+        /// The closest unit variant pattern to the input, if one was close enough.
+        suggestion: Option<&'static str>,
+    },
+    /// The variant returned when a derived type was parsed but failed its validation.
+    /// When not using the `alloc` feature, `Validation` does not contain the error.
+    #[cfg(feature = "alloc")]
+    Validation {
+        /// The name of the field that failed its validation,
+        /// `None` if the validation of the whole struct or variant failed.
+        field: Option<&'static str>,
+        /// The error returned by the validation.
+        error: Box<ParseError>,
+    },
+    /// The variant returned when a derived type was parsed but failed its validation.
+    /// When not using the `alloc` feature, `Validation` does not contain the error.
+    #[cfg(not(feature = "alloc"))]
+    Validation {
+        /// The name of the field that failed its validation,
+        /// `None` if the validation of the whole struct or variant failed.
+        field: Option<&'static str>,
+    },
 }
 
 #[cfg(feature = "alloc")]
@@ -135,7 +171,12 @@ impl error::Error for ParseError {
             ParseError::Dyn(source) => Some(&**source),
             ParseError::MultiContext { error, .. } => Some(error),
             ParseError::Context { error, .. } => Some(error),
-            ParseError::Literal { .. } | ParseError::Array { .. } | ParseError::Other(_) => None,
+            ParseError::Validation { error, .. } => Some(error),
+            ParseError::Literal { .. }
+            | ParseError::Array { .. }
+            | ParseError::Width { .. }
+            | ParseError::Other(_)
+            | ParseError::Variants { .. } => None,
         }
     }
 }
@@ -162,6 +203,10 @@ impl core::fmt::Display for ParseError {
                 fmt,
                 "invalid number of items (expected to find {expected:?}, found {found:?})"
             ),
+            ParseError::Width { expected, found } => write!(
+                fmt,
+                "invalid width (expected to find {expected:?}, found {found:?})"
+            ),
             #[cfg(feature = "alloc")]
             ParseError::Other(message) => write!(fmt, "{message}"),
             #[cfg(not(feature = "alloc"))]
@@ -188,6 +233,32 @@ impl core::fmt::Display for ParseError {
                     "unable to parse \"{failed_item}\" when parsing \"{full_string}\":\n\t{error}"
                 )
             }
+            #[cfg(feature = "alloc")]
+            ParseError::Variants { errors, suggestion } => {
+                write!(fmt, "unable to parse any variant")?;
+                if let Some(suggestion) = suggestion {
+                    write!(fmt, ", did you mean {suggestion:?}?")?;
+                }
+                for (variant, error) in errors {
+                    write!(fmt, "\n\t{variant}: {error}")?;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "alloc")]
+            ParseError::Validation {
+                field: Some(field),
+                error,
+            } => write!(fmt, "validation failed for field {field}:\n\t{error}"),
+            #[cfg(feature = "alloc")]
+            ParseError::Validation { field: None, error } => {
+                write!(fmt, "validation failed:\n\t{error}")
+            }
+            #[cfg(not(feature = "alloc"))]
+            ParseError::Validation { field: Some(field) } => {
+                write!(fmt, "validation failed for field {field}")
+            }
+            #[cfg(not(feature = "alloc"))]
+            ParseError::Validation { field: None } => write!(fmt, "validation failed"),
         }
     }
 }
@@ -226,6 +297,16 @@ impl PartialEq for ParseError {
                     found: ry,
                 },
             ) => lx == rx && ly == ry,
+            (
+                E::Width {
+                    expected: lx,
+                    found: ly,
+                },
+                E::Width {
+                    expected: rx,
+                    found: ry,
+                },
+            ) => lx == rx && ly == ry,
             #[cfg(feature = "alloc")]
             (E::Other(x), E::Other(y)) => x == y,
             #[cfg(not(feature = "alloc"))]
@@ -256,6 +337,30 @@ impl PartialEq for ParseError {
                     error: re,
                 },
             ) => ls == rs && lf == rf && le == re,
+            #[cfg(feature = "alloc")]
+            (
+                E::Variants {
+                    errors: le,
+                    suggestion: ls,
+                },
+                E::Variants {
+                    errors: re,
+                    suggestion: rs,
+                },
+            ) => le == re && ls == rs,
+            #[cfg(feature = "alloc")]
+            (
+                E::Validation {
+                    field: lf,
+                    error: le,
+                },
+                E::Validation {
+                    field: rf,
+                    error: re,
+                },
+            ) => lf == rf && le == re,
+            #[cfg(not(feature = "alloc"))]
+            (E::Validation { field: lf }, E::Validation { field: rf }) => lf == rf,
             _ => false,
         }
     }
@@ -297,8 +402,11 @@ impl From<()> for ParseError {
 #[doc(hidden)]
 pub mod __private {
     #[cfg(feature = "alloc")]
-    use super::{Box, ToString};
-    use crate::{ExtParseStr, Parse, ParseError};
+    use super::{Box, String, ToString, Vec};
+    use crate::parse_iterators::find_nested;
+    use crate::{Parse, ParseError, ParseWith};
+    use core::fmt;
+    use core::ops::RangeBounds;
 
     #[doc(hidden)]
     /// Not part of public api used to unwrap the result when parsing.
@@ -309,13 +417,162 @@ pub mod __private {
         }
     }
 
+    #[doc(hidden)]
+    /// Not part of public api used to find a literal that is not inside brackets or quotes.
+    pub fn split_once_nested<'a>(input: &'a str, literal: &str) -> Option<(&'a str, &'a str)> {
+        let finder = memchr::memmem::Finder::new(literal);
+        find_nested(input, |h| {
+            finder
+                .find(h.as_bytes())
+                .map(|start| (start, start + literal.len()))
+        })
+        .map(|(start, end)| (&input[..start], &input[end..]))
+    }
+
+    #[doc(hidden)]
+    /// Not part of public api used to split off a fixed width from the start of the input.
+    pub fn split_width(
+        input: &str,
+        width: usize,
+        is_chars: bool,
+    ) -> Result<(&str, &str), ParseError> {
+        let idx = if is_chars {
+            char_boundaries(input).nth(width)
+        } else {
+            Some(width)
+        };
+        match idx.filter(|&idx| input.is_char_boundary(idx)) {
+            Some(idx) => Ok(input.split_at(idx)),
+            None => Err(width_error(input, width, is_chars)),
+        }
+    }
+
+    #[doc(hidden)]
+    /// Not part of public api used to split off a fixed width from the end of the input.
+    pub fn rsplit_width(
+        input: &str,
+        width: usize,
+        is_chars: bool,
+    ) -> Result<(&str, &str), ParseError> {
+        let idx = if is_chars {
+            char_boundaries(input).rev().nth(width)
+        } else {
+            input.len().checked_sub(width)
+        };
+        match idx.filter(|&idx| input.is_char_boundary(idx)) {
+            Some(idx) => Ok(input.split_at(idx)),
+            None => Err(width_error(input, width, is_chars)),
+        }
+    }
+
+    fn char_boundaries(input: &str) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        input
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .chain(Some(input.len()))
+    }
+
+    fn width_error(input: &str, width: usize, is_chars: bool) -> ParseError {
+        ParseError::Width {
+            expected: width,
+            found: if is_chars {
+                input.chars().count()
+            } else {
+                input.len()
+            },
+        }
+    }
+
     #[doc(hidden)]
     #[cfg(not(feature = "alloc"))]
     pub fn try_parse_context<'a, T: Parse<'a>>(
         item: &'a str,
         _full_string: &'a str,
+        input: &'a str,
     ) -> Result<T, ParseError> {
-        item.lending_parse()
+        T::from_str_in(item, input)
+    }
+
+    #[doc(hidden)]
+    #[cfg(not(feature = "alloc"))]
+    pub fn try_parse_context_with<'a, C: ?Sized, T: ParseWith<'a, C>>(
+        item: &'a str,
+        _full_string: &'a str,
+        input: &'a str,
+        ctx: &mut C,
+    ) -> Result<T, ParseError> {
+        T::from_str_with_in(item, input, ctx)
+    }
+
+    #[doc(hidden)]
+    #[cfg(not(feature = "alloc"))]
+    pub fn add_err_context<T, E: Into<ParseError>>(
+        result: Result<T, E>,
+        _full_string: &str,
+        _item: &str,
+    ) -> Result<T, ParseError> {
+        result.map_err(|e| e.into())
+    }
+
+    #[doc(hidden)]
+    #[cfg(not(feature = "alloc"))]
+    /// Not part of public api used to convert the result of a validation function.
+    pub fn validate<E: Into<ParseError>>(
+        result: Result<(), E>,
+        field: Option<&'static str>,
+    ) -> Result<(), ParseError> {
+        result.map_err(|_| ParseError::Validation { field })
+    }
+
+    #[doc(hidden)]
+    #[cfg(not(feature = "alloc"))]
+    /// Not part of public api used to check that a field is in its range.
+    pub fn validate_range<T: PartialOrd, R: RangeBounds<T>>(
+        value: &T,
+        range: R,
+        field: &'static str,
+        _range_str: &'static str,
+    ) -> Result<(), ParseError> {
+        if range.contains(value) {
+            Ok(())
+        } else {
+            Err(ParseError::Validation { field: Some(field) })
+        }
+    }
+
+    #[doc(hidden)]
+    #[cfg(not(feature = "alloc"))]
+    /// Not part of public api used when a key-value struct is missing a key.
+    pub fn missing_key(_key: &str) -> ParseError {
+        ParseError::Other
+    }
+
+    #[doc(hidden)]
+    #[cfg(not(feature = "alloc"))]
+    /// Not part of public api used when a key-value struct finds an unknown key.
+    pub fn unknown_key(_key: &str) -> ParseError {
+        ParseError::Other
+    }
+
+    #[doc(hidden)]
+    #[cfg(not(feature = "alloc"))]
+    /// Not part of public api used when a key-value struct finds a key twice.
+    pub fn duplicate_key(_key: &str) -> ParseError {
+        ParseError::Other
+    }
+
+    #[doc(hidden)]
+    #[cfg(not(feature = "alloc"))]
+    /// Not part of public api used when a number is not the discriminant of any variant.
+    pub fn unknown_discriminant(_discriminant: i128) -> ParseError {
+        ParseError::Other
+    }
+
+    #[doc(hidden)]
+    #[cfg(not(feature = "alloc"))]
+    /// Not part of public api used when a tag does not select any variant.
+    pub fn unknown_tag(_name: &str, _tag: &str) -> ParseError {
+        ParseError::Other
     }
 
     #[doc(hidden)]
@@ -333,14 +590,140 @@ pub mod __private {
     pub fn try_parse_context<'a, T: Parse<'a>>(
         item: &'a str,
         full_string: &'a str,
+        input: &'a str,
     ) -> Result<T, ParseError> {
-        item.lending_parse().map_err(|e| ParseError::Context {
+        T::from_str_in(item, input).map_err(|e| ParseError::Context {
             full_string: full_string.to_string(),
             failed_item: item.to_string(),
             error: Box::new(e),
         })
     }
 
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    pub fn try_parse_context_with<'a, C: ?Sized, T: ParseWith<'a, C>>(
+        item: &'a str,
+        full_string: &'a str,
+        input: &'a str,
+        ctx: &mut C,
+    ) -> Result<T, ParseError> {
+        T::from_str_with_in(item, input, ctx).map_err(|e| ParseError::Context {
+            full_string: full_string.to_string(),
+            failed_item: item.to_string(),
+            error: Box::new(e),
+        })
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    pub fn add_err_context<T, E: Into<ParseError>>(
+        result: Result<T, E>,
+        full_string: &str,
+        item: &str,
+    ) -> Result<T, ParseError> {
+        result.map_err(|e| ParseError::Context {
+            full_string: full_string.to_string(),
+            failed_item: item.to_string(),
+            error: Box::new(e.into()),
+        })
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    /// Not part of public api used to convert the result of a validation function.
+    pub fn validate<E: Into<ParseError>>(
+        result: Result<(), E>,
+        field: Option<&'static str>,
+    ) -> Result<(), ParseError> {
+        result.map_err(|e| ParseError::Validation {
+            field,
+            error: Box::new(e.into()),
+        })
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    /// Not part of public api used to check that a field is in its range.
+    pub fn validate_range<T: PartialOrd, R: RangeBounds<T>>(
+        value: &T,
+        range: R,
+        field: &'static str,
+        range_str: &'static str,
+    ) -> Result<(), ParseError> {
+        if range.contains(value) {
+            Ok(())
+        } else {
+            Err(ParseError::Validation {
+                field: Some(field),
+                error: Box::new(ParseError::Other(
+                    "expected a value in the range ".to_string() + range_str,
+                )),
+            })
+        }
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    /// Not part of public api used when a key-value struct is missing a key.
+    pub fn missing_key(key: &str) -> ParseError {
+        ParseError::new(format_args!("missing key {key:?}"))
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    /// Not part of public api used when a key-value struct finds an unknown key.
+    pub fn unknown_key(key: &str) -> ParseError {
+        ParseError::new(format_args!("unknown key {key:?}"))
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    /// Not part of public api used when a key-value struct finds a key twice.
+    pub fn duplicate_key(key: &str) -> ParseError {
+        ParseError::new(format_args!("duplicate key {key:?}"))
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    /// Not part of public api used when a number is not the discriminant of any variant.
+    pub fn unknown_discriminant(discriminant: i128) -> ParseError {
+        ParseError::new(format_args!("unknown discriminant {discriminant}"))
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    /// Not part of public api used when a tag does not select any variant.
+    pub fn unknown_tag(name: &str, tag: &str) -> ParseError {
+        ParseError::new(format_args!("unknown {name} {tag:?}"))
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    /// Not part of public api used to find the closest unit variant pattern to the input.
+    pub fn suggest(input: &str, candidates: &[&'static str]) -> Option<&'static str> {
+        candidates
+            .iter()
+            .map(|c| (levenshtein(input, c), *c))
+            .filter(|(distance, c)| *distance <= input.chars().count().max(c.chars().count()) / 3)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, c)| c)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn levenshtein(a: &str, b: &str) -> usize {
+        let mut row: Vec<usize> = (0..=b.chars().count()).collect();
+        for (i, ca) in a.chars().enumerate() {
+            let mut prev = row[0];
+            row[0] = i + 1;
+            for (j, cb) in b.chars().enumerate() {
+                let substitution = prev + usize::from(ca != cb);
+                prev = row[j + 1];
+                row[j + 1] = substitution.min(prev + 1).min(row[j] + 1);
+            }
+        }
+        row[row.len() - 1]
+    }
+
     #[doc(hidden)]
     #[cfg(feature = "alloc")]
     pub fn add_err_multi_context<T>(
@@ -354,12 +737,115 @@ pub mod __private {
             error: Box::new(e),
         })
     }
+
+    #[doc(hidden)]
+    /// Not part of public api used to parse an item of a repetition split from `multi_string` using a context.
+    pub fn parse_item_with<'a, C: ?Sized, T: ParseWith<'a, C>>(
+        item: Result<&'a str, ParseError>,
+        multi_string: &'a str,
+        input: &'a str,
+        ctx: &mut C,
+    ) -> Result<T, ParseError> {
+        let item = item?;
+        add_err_multi_context(T::from_str_with_in(item, input, ctx), multi_string, item)
+    }
+
+    #[doc(hidden)]
+    /// Not part of public api used to display a value using a function.
+    pub struct DisplayFn<F>(F);
+
+    impl<F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result> fmt::Display for DisplayFn<F> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            (self.0)(f)
+        }
+    }
+
+    #[doc(hidden)]
+    /// Not part of public api used to display a field using its `display_with` function.
+    pub fn display_fn<F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result>(f: F) -> DisplayFn<F> {
+        DisplayFn(f)
+    }
+
+    /// Counts the bytes and chars written to it.
+    #[derive(Default)]
+    struct Count {
+        bytes: usize,
+        chars: usize,
+    }
+
+    impl fmt::Write for Count {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.bytes += s.len();
+            self.chars += s.chars().count();
+            Ok(())
+        }
+    }
+
+    impl Count {
+        fn of<T: fmt::Display + ?Sized>(value: &T, min_width: usize) -> Result<Self, fmt::Error> {
+            let mut count = Count::default();
+            fmt::Write::write_fmt(&mut count, format_args!("{value:0min_width$}"))?;
+            Ok(count)
+        }
+
+        fn len(&self, is_chars: bool) -> usize {
+            if is_chars {
+                self.chars
+            } else {
+                self.bytes
+            }
+        }
+    }
+
+    #[doc(hidden)]
+    /// Not part of public api used to write a fixed width capture padded to exactly its width.
+    pub fn write_fixed<T: fmt::Display + ?Sized>(
+        f: &mut fmt::Formatter<'_>,
+        value: &T,
+        width: usize,
+        is_chars: bool,
+    ) -> fmt::Result {
+        let unpadded = Count::of(value, 0)?;
+        // A value that does not fit would not be parsed back into the same value.
+        let padding = width
+            .checked_sub(unpadded.len(is_chars))
+            .ok_or(fmt::Error)?;
+        // The padding is made of single byte characters so it adds as many bytes as chars.
+        let min_width = unpadded.chars + padding;
+        let spaces = width
+            .checked_sub(Count::of(value, min_width)?.len(is_chars))
+            .ok_or(fmt::Error)?;
+        write!(f, "{value:0min_width$}")?;
+        // Types that ignore the width are padded with spaces instead.
+        for _ in 0..spaces {
+            f.write_str(" ")?;
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "alloc")]
+    /// Not part of public api used to write the captures of the unparse macro into a string.
+    pub fn unparse<F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result>(f: F) -> String {
+        DisplayFn(f).to_string()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::ParseError;
 
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn check_suggestions() {
+        use super::__private::suggest;
+        let candidates = ["north", "south", "east", "west"];
+        assert_eq!(suggest("nort", &candidates), Some("north"));
+        assert_eq!(suggest("South", &candidates), Some("south"));
+        assert_eq!(suggest("up", &candidates), None);
+        assert_eq!(suggest("", &candidates), None);
+    }
+
     #[test]
     fn check_impl_traits() {
         fn is_send<T: Send>() {}
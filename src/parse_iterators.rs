@@ -1,8 +1,192 @@
-use crate::{Parse, ParseError, __private};
+use crate::{__private, Parse, ParseError};
 use core::iter::FusedIterator;
 use core::marker::PhantomData;
+use core::ops::Range;
 use core::str::CharIndices;
-use memchr::memmem::FindIter;
+use memchr::memmem::{Finder, FinderRev};
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// The separator that [`ParseIter`] uses to split up its string.
+///
+/// It is produced from the separator of [`parse!`](crate::parse) and [`try_parse!`](crate::try_parse)'s
+/// repetition syntax, `{:[,;]:}` creates an [`AnyOf`](Separator::AnyOf) separator
+/// and `{:\s:}` creates a [`Whitespace`](Separator::Whitespace) separator.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Separator<'a> {
+    /// Splits the string on each occurrence of the string slice.
+    Str(&'a str),
+    /// Splits the string on each occurrence of any of the string slices.
+    ///
+    /// If multiple string slices match at the same position, the first one is used.
+    AnyOf(&'a [&'a str]),
+    /// Splits the string on runs of whitespace, similar to [`str::split_whitespace`].
+    Whitespace,
+}
+
+/// Returns the first match of `find` that is not inside of brackets or a quoted string.
+pub(crate) fn find_nested(
+    haystack: &str,
+    mut find: impl FnMut(&str) -> Option<(usize, usize)>,
+) -> Option<(usize, usize)> {
+    let mut nesting = Nesting::default();
+    let mut scanned = 0;
+    loop {
+        let (start, end) = find(&haystack[scanned..]).map(|(s, e)| (scanned + s, scanned + e))?;
+        nesting.scan(&haystack.as_bytes()[scanned..start]);
+        if nesting.is_top_level() {
+            return Some((start, end));
+        }
+        // Step over the first character of the match so that it is not found again.
+        scanned = start + haystack[start..].chars().next()?.len_utf8();
+        nesting.scan(&haystack.as_bytes()[start..scanned]);
+    }
+}
+
+/// Keeps track of the brackets and quotes that have been opened while scanning a string.
+#[derive(Default)]
+struct Nesting {
+    depth: usize,
+    in_quote: bool,
+    is_escaped: bool,
+}
+
+impl Nesting {
+    fn scan(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            match (self.in_quote, b) {
+                (true, _) if self.is_escaped => self.is_escaped = false,
+                (true, b'\\') => self.is_escaped = true,
+                // A doubled up quote closes and then reopens the quoted string.
+                (_, b'"') => self.in_quote = !self.in_quote,
+                (false, b'(' | b'[' | b'{') => self.depth += 1,
+                (false, b')' | b']' | b'}') => self.depth = self.depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+
+    fn is_top_level(&self) -> bool {
+        self.depth == 0 && !self.in_quote
+    }
+}
+
+/// Returns the last match of `find` that is not inside of brackets or a quoted string.
+fn rfind_nested(
+    haystack: &str,
+    mut find: impl FnMut(&str) -> Option<(usize, usize)>,
+) -> Option<(usize, usize)> {
+    // Nesting can only be tracked from the start of the string, so find every match and keep the last one.
+    let mut found = None;
+    let mut scanned = 0;
+    while let Some((start, end)) = find_nested(&haystack[scanned..], &mut find) {
+        found = Some((scanned + start, scanned + end));
+        scanned += end;
+    }
+    found
+}
+
+/// The distinct first bytes of a set of separators, used to skip to the positions where one of them can start.
+struct FirstBytes {
+    bytes: [u8; 256],
+    len: usize,
+    set: [bool; 256],
+}
+
+impl FirstBytes {
+    fn new(needles: &[&str]) -> Self {
+        let mut first = FirstBytes {
+            bytes: [0; 256],
+            len: 0,
+            set: [false; 256],
+        };
+        for b in needles.iter().filter_map(|n| n.as_bytes().first()) {
+            if !first.set[*b as usize] {
+                first.set[*b as usize] = true;
+                first.bytes[first.len] = *b;
+                first.len += 1;
+            }
+        }
+        first
+    }
+
+    fn find(&self, haystack: &[u8]) -> Option<usize> {
+        match self.bytes[..self.len] {
+            [a] => memchr::memchr(a, haystack),
+            [a, b] => memchr::memchr2(a, b, haystack),
+            [a, b, c] => memchr::memchr3(a, b, c, haystack),
+            _ => haystack.iter().position(|b| self.set[*b as usize]),
+        }
+    }
+
+    fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        match self.bytes[..self.len] {
+            [a] => memchr::memrchr(a, haystack),
+            [a, b] => memchr::memrchr2(a, b, haystack),
+            [a, b, c] => memchr::memrchr3(a, b, c, haystack),
+            _ => haystack.iter().rposition(|b| self.set[*b as usize]),
+        }
+    }
+}
+
+/// Returns the first separator in `needles` that starts at `start`.
+fn match_any_of(haystack: &[u8], start: usize, needles: &[&str]) -> Option<(usize, usize)> {
+    needles
+        .iter()
+        .find(|n| haystack[start..].starts_with(n.as_bytes()))
+        .map(|n| (start, start + n.len()))
+}
+
+fn find_any_of(haystack: &[u8], needles: &[&str]) -> Option<(usize, usize)> {
+    match needles {
+        [a, b] if a.len() == 1 && b.len() == 1 => {
+            memchr::memchr2(a.as_bytes()[0], b.as_bytes()[0], haystack).map(|i| (i, i + 1))
+        }
+        [a, b, c] if a.len() == 1 && b.len() == 1 && c.len() == 1 => {
+            memchr::memchr3(a.as_bytes()[0], b.as_bytes()[0], c.as_bytes()[0], haystack)
+                .map(|i| (i, i + 1))
+        }
+        _ => {
+            // The haystack is scanned once, only checking the separators at the positions where one can start.
+            let first = FirstBytes::new(needles);
+            let mut offset = 0;
+            while let Some(start) = first.find(&haystack[offset..]) {
+                let start = offset + start;
+                if let Some(found) = match_any_of(haystack, start, needles) {
+                    return Some(found);
+                }
+                offset = start + 1;
+            }
+            None
+        }
+    }
+}
+
+fn rfind_any_of(haystack: &[u8], needles: &[&str]) -> Option<(usize, usize)> {
+    match needles {
+        [a, b] if a.len() == 1 && b.len() == 1 => {
+            memchr::memrchr2(a.as_bytes()[0], b.as_bytes()[0], haystack).map(|i| (i, i + 1))
+        }
+        [a, b, c] if a.len() == 1 && b.len() == 1 && c.len() == 1 => {
+            memchr::memrchr3(a.as_bytes()[0], b.as_bytes()[0], c.as_bytes()[0], haystack)
+                .map(|i| (i, i + 1))
+        }
+        _ => {
+            let first = FirstBytes::new(needles);
+            let mut end = haystack.len();
+            while let Some(start) = first.rfind(&haystack[..end]) {
+                if let Some(found) = match_any_of(haystack, start, needles) {
+                    return Some(found);
+                }
+                end = start;
+            }
+            None
+        }
+    }
+}
 
 /// An iterator that takes a string and parses all items between each separator.
 ///
@@ -24,73 +208,227 @@ use memchr::memmem::FindIter;
 /// assert_eq!(game.results.next(), Some(Ok(3)));
 /// assert_eq!(game.results.next(), None);
 /// ```
+///
+/// It can also be created directly and iterated from either end.
+///
+/// ```
+/// # use prse::{ParseIter, Separator};
+/// let mut iter: ParseIter<u32> = ParseIter::new("1, 2, 3, 4", Separator::Str(", "));
+/// assert_eq!(iter.next(), Some(Ok(1)));
+/// assert_eq!(iter.next_back(), Some(Ok(4)));
+/// assert_eq!(iter.remainder(), "2, 3");
+/// ```
+///
+/// When iterating backwards, a separator that can overlap with itself (e.g. `aa` in `aaa`)
+/// will be matched from the back of the string instead of the front.
 #[derive(Debug, Clone)]
 pub struct ParseIter<'a, T: Parse<'a>> {
-    finder: FindIter<'a, 'a>,
+    separator: Separator<'a>,
+    finder: Finder<'a>,
+    finder_rev: FinderRev<'a>,
     is_multi: bool,
+    is_nested: bool,
     separator_size: usize,
     string: &'a str,
-    last_match_idx: usize,
+    input: &'a str,
+    front: usize,
+    back: usize,
+    finished: bool,
     phantom: PhantomData<T>,
 }
 
 impl<'a, T: Parse<'a>> ParseIter<'a, T> {
-    #[doc(hidden)]
-    /// Not part of public api, used to create the iterator.
-    pub fn new(string: &'a str, separator: &'a str, is_multi: bool) -> Self {
+    /// Creates an iterator that parses each item of `string` that is between a `separator`.
+    ///
+    /// An empty item at the end of the string is always skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator is (or contains) an empty string slice,
+    /// [`ParseChars`] should be used to iterate over each char instead.
+    pub fn new(string: &'a str, separator: Separator<'a>) -> Self {
+        let (needle, separator_size) = match separator {
+            Separator::Str(s) => (s, s.len()),
+            Separator::AnyOf(s) => ("", s.iter().map(|s| s.len()).min().unwrap_or(usize::MAX)),
+            Separator::Whitespace => ("", 1),
+        };
+        assert_ne!(separator_size, 0, "separator must be non-empty");
         Self {
-            finder: memchr::memmem::find_iter(string.as_bytes(), separator.as_bytes()),
-            separator_size: separator.as_bytes().len(),
-            is_multi,
+            separator,
+            finder: Finder::new(needle),
+            finder_rev: FinderRev::new(needle),
+            separator_size,
+            is_multi: separator == Separator::Whitespace,
+            is_nested: false,
             string,
-            last_match_idx: 0,
+            input: string,
+            front: 0,
+            back: string.len(),
+            finished: false,
             phantom: PhantomData,
         }
     }
-}
 
-impl<'a, T: Parse<'a>> Iterator for ParseIter<'a, T> {
-    type Item = Result<T, ParseError>;
+    /// Skips every empty item, so that multiple separators in a row are treated as one.
+    ///
+    /// This is what the `!` modifier does (e.g. `{:-:!0}`).
+    ///
+    /// ```
+    /// # use prse::{ParseIter, Separator};
+    /// let iter: ParseIter<u32> = ParseIter::new("1--2---3", Separator::Str("-")).skip_empty();
+    /// assert_eq!(iter.collect::<Result<Vec<_>, _>>(), Ok(vec![1, 2, 3]));
+    /// ```
+    pub fn skip_empty(mut self) -> Self {
+        self.is_multi = true;
+        self
+    }
+
+    /// Ignores any separators that are inside of `()`, `[]` or `{}` brackets, or inside of `"` quoted strings.
+    ///
+    /// This is what the `#` modifier does (e.g. `{:,:#0}`).
+    ///
+    /// ```
+    /// # use prse::{ParseIter, Separator};
+    /// let mut iter: ParseIter<&str> = ParseIter::new("(1,2),3", Separator::Str(",")).nested();
+    /// assert_eq!(iter.next(), Some(Ok("(1,2)")));
+    /// assert_eq!(iter.next(), Some(Ok("3")));
+    /// ```
+    pub fn nested(mut self) -> Self {
+        self.is_nested = true;
+        self
+    }
+
+    /// Returns the part of the string that has not been iterated over yet.
+    pub fn remainder(&self) -> &'a str {
+        &self.string[self.front..self.back]
+    }
+
+    fn find_separator(&self, haystack: &str) -> Option<(usize, usize)> {
+        if self.is_nested {
+            find_nested(haystack, |h| self.find_any_separator(h))
+        } else {
+            self.find_any_separator(haystack)
+        }
+    }
+
+    fn rfind_separator(&self, haystack: &str) -> Option<(usize, usize)> {
+        if self.is_nested {
+            rfind_nested(haystack, |h| self.find_any_separator(h))
+        } else {
+            self.rfind_any_separator(haystack)
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        for idx in self.finder.by_ref() {
-            if let Some(slice) = self.string.get(self.last_match_idx..idx) {
-                self.last_match_idx = idx + self.separator_size;
-                if !self.is_multi || !slice.is_empty() {
-                    return Some(__private::add_err_multi_context(
-                        T::from_str(slice),
-                        self.string,
-                        slice,
-                    ));
+    /// Returns the start and end of the first separator in `haystack`.
+    fn find_any_separator(&self, haystack: &str) -> Option<(usize, usize)> {
+        match self.separator {
+            Separator::Str(s) => self
+                .finder
+                .find(haystack.as_bytes())
+                .map(|start| (start, start + s.len())),
+            Separator::AnyOf(needles) => find_any_of(haystack.as_bytes(), needles),
+            Separator::Whitespace => {
+                let start = haystack.find(char::is_whitespace)?;
+                let end = haystack[start..]
+                    .find(|c: char| !c.is_whitespace())
+                    .map_or(haystack.len(), |end| start + end);
+                Some((start, end))
+            }
+        }
+    }
+
+    /// Returns the start and end of the last separator in `haystack`.
+    fn rfind_any_separator(&self, haystack: &str) -> Option<(usize, usize)> {
+        match self.separator {
+            Separator::Str(s) => self
+                .finder_rev
+                .rfind(haystack.as_bytes())
+                .map(|start| (start, start + s.len())),
+            Separator::AnyOf(needles) => rfind_any_of(haystack.as_bytes(), needles),
+            Separator::Whitespace => {
+                let last = haystack.rfind(char::is_whitespace)?;
+                let end = last + haystack[last..].chars().next()?.len_utf8();
+                let start = haystack[..end].trim_end_matches(char::is_whitespace).len();
+                Some((start, end))
+            }
+        }
+    }
+
+    fn is_skipped(&self, range: &Range<usize>) -> bool {
+        range.is_empty() && (self.is_multi || range.end == self.string.len())
+    }
+
+    fn next_range(&mut self) -> Option<Range<usize>> {
+        while !self.finished {
+            let range = match self.find_separator(self.remainder()) {
+                Some((start, end)) => {
+                    let range = self.front..(self.front + start);
+                    self.front += end;
+                    range
                 }
+                None => {
+                    self.finished = true;
+                    let range = self.front..self.back;
+                    self.front = self.back;
+                    range
+                }
+            };
+            if !self.is_skipped(&range) {
+                return Some(range);
             }
         }
-        if let Some(slice) = self.string.get(self.last_match_idx..) {
-            self.last_match_idx = self.string.len() + 1;
-            if !slice.is_empty() {
-                return Some(__private::add_err_multi_context(
-                    T::from_str(slice),
-                    self.string,
-                    slice,
-                ));
+        None
+    }
+
+    fn next_back_range(&mut self) -> Option<Range<usize>> {
+        while !self.finished {
+            let range = match self.rfind_separator(self.remainder()) {
+                Some((start, end)) => {
+                    let range = (self.front + end)..self.back;
+                    self.back = self.front + start;
+                    range
+                }
+                None => {
+                    self.finished = true;
+                    let range = self.front..self.back;
+                    self.back = self.front;
+                    range
+                }
+            };
+            if !self.is_skipped(&range) {
+                return Some(range);
             }
         }
         None
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        match (
-            self.string.len().checked_sub(self.last_match_idx),
-            self.is_multi,
-        ) {
-            (None | Some(0), _) => (0, Some(0)),
-            (Some(haystack_len), false) => (0, Some(1 + haystack_len / self.separator_size)),
-            (Some(haystack_len), true) => (0, Some(1 + haystack_len / (self.separator_size + 1))),
+    /// Finds the last item by splitting forwards, as a separator that overlaps with itself
+    /// can be matched at a different position when splitting backwards.
+    fn last_range(&mut self) -> Option<Range<usize>> {
+        let mut last = None;
+        while let Some(range) = self.next_range() {
+            last = Some(range);
         }
+        last
     }
-}
 
-impl<'a, T: Parse<'a>> FusedIterator for ParseIter<'a, T> {}
+    fn parse_range(&self, range: Range<usize>) -> Result<T, ParseError> {
+        let slice = &self.string[range];
+        __private::add_err_multi_context(T::from_str_in(slice, self.input), self.string, slice)
+    }
+
+    fn range_size_hint(&self) -> (usize, Option<usize>) {
+        match (self.back - self.front, self.is_multi) {
+            _ if self.finished => (0, Some(0)),
+            (0, _) => (0, Some(1)),
+            (haystack_len, false) => (0, Some(1 + haystack_len / self.separator_size)),
+            (haystack_len, true) => (
+                0,
+                Some(1 + haystack_len / self.separator_size.saturating_add(1)),
+            ),
+        }
+    }
+}
 
 /// An iterator that takes a string and parses all chars individually.
 ///
@@ -121,51 +459,295 @@ impl<'a, T: Parse<'a>> FusedIterator for ParseIter<'a, T> {}
 pub struct ParseChars<'a, T: Parse<'a>> {
     chars: CharIndices<'a>,
     string: &'a str,
+    input: &'a str,
     phantom: PhantomData<T>,
 }
 
 impl<'a, T: Parse<'a>> ParseChars<'a, T> {
-    #[doc(hidden)]
-    /// Not part of public api, used to create the iterator.
+    /// Creates an iterator that parses each char of `string`.
     pub fn new(string: &'a str) -> Self {
         Self {
             chars: string.char_indices(),
             string,
+            input: string,
             phantom: PhantomData,
         }
     }
+
+    /// Returns the part of the string that has not been iterated over yet.
+    ///
+    /// ```
+    /// # use prse::ParseChars;
+    /// let mut iter: ParseChars<char> = ParseChars::new("abc");
+    /// iter.next();
+    /// assert_eq!(iter.remainder(), "bc");
+    /// ```
+    pub fn remainder(&self) -> &'a str {
+        self.chars.as_str()
+    }
+
+    fn next_range(&mut self) -> Option<Range<usize>> {
+        self.chars
+            .next()
+            .map(|(start, c)| start..(start + c.len_utf8()))
+    }
+
+    fn next_back_range(&mut self) -> Option<Range<usize>> {
+        self.chars
+            .next_back()
+            .map(|(start, c)| start..(start + c.len_utf8()))
+    }
+
+    fn last_range(&mut self) -> Option<Range<usize>> {
+        self.next_back_range()
+    }
+
+    fn parse_range(&self, range: Range<usize>) -> Result<T, ParseError> {
+        let slice = &self.string[range];
+        __private::add_err_multi_context(T::from_str_in(slice, self.input), self.string, slice)
+    }
+
+    fn range_size_hint(&self) -> (usize, Option<usize>) {
+        self.chars.size_hint()
+    }
 }
 
-impl<'a, T: Parse<'a>> Iterator for ParseChars<'a, T> {
-    type Item = Result<T, ParseError>;
+/// An iterator that takes a string and parses fixed size chunks of chars.
+///
+/// It is produced from [`parse!`](crate::parse) and [`try_parse!`](crate::try_parse)'s Iterator
+/// repetition when given a `~<size>` separator.
+///
+/// ```
+/// # use prse::{parse, try_parse, ParseChunks, ParseError};
+/// let mut codons: ParseChunks<&str> = parse!("AUGGCCUAA", "{:~3:0}");
+/// assert_eq!(codons.len(), 3);
+/// assert_eq!(codons.next(), Some(Ok("AUG")));
+/// assert_eq!(codons.next_back(), Some(Ok("UAA")));
+/// assert_eq!(codons.next(), Some(Ok("GCC")));
+/// assert_eq!(codons.next(), None);
+///
+/// let incomplete: Result<ParseChunks<&str>, _> = try_parse!("AUGGC", "{:~3:0}");
+/// assert_eq!(incomplete.unwrap_err(), ParseError::Width { expected: 3, found: 2 });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParseChunks<'a, T: Parse<'a>> {
+    string: &'a str,
+    input: &'a str,
+    chunk_size: usize,
+    front: usize,
+    back: usize,
+    remaining: usize,
+    phantom: PhantomData<T>,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.chars.next().map(|(start, c)| {
-            let slice = self.string.get(start..(start + c.len_utf8())).unwrap();
-            __private::add_err_multi_context(T::from_str(slice), self.string, slice)
+impl<'a, T: Parse<'a>> ParseChunks<'a, T> {
+    /// Creates an iterator that parses each chunk of `chunk_size` chars in `string`.
+    ///
+    /// Returns a [`Width`](ParseError::Width) error if the amount of chars in `string`
+    /// is not a multiple of `chunk_size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn new(string: &'a str, chunk_size: usize) -> Result<Self, ParseError> {
+        assert_ne!(chunk_size, 0, "chunk size must be non-zero");
+        let count = string.chars().count();
+        if count % chunk_size != 0 {
+            return Err(ParseError::Width {
+                expected: chunk_size,
+                found: count % chunk_size,
+            });
+        }
+        Ok(Self {
+            string,
+            input: string,
+            chunk_size,
+            front: 0,
+            back: string.len(),
+            remaining: count / chunk_size,
+            phantom: PhantomData,
         })
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        self.chars.size_hint()
+    /// Returns the part of the string that has not been iterated over yet.
+    pub fn remainder(&self) -> &'a str {
+        &self.string[self.front..self.back]
     }
 
-    fn count(self) -> usize {
-        self.chars.count()
+    fn next_range(&mut self) -> Option<Range<usize>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let start = self.front;
+        self.front = self.string[start..self.back]
+            .char_indices()
+            .nth(self.chunk_size)
+            .map_or(self.back, |(idx, _)| start + idx);
+        Some(start..self.front)
     }
 
-    fn last(mut self) -> Option<Self::Item> {
-        self.next_back()
+    fn next_back_range(&mut self) -> Option<Range<usize>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let end = self.back;
+        self.back = self.string[self.front..end]
+            .char_indices()
+            .nth_back(self.chunk_size - 1)
+            .map_or(self.front, |(idx, _)| self.front + idx);
+        Some(self.back..end)
+    }
+
+    fn last_range(&mut self) -> Option<Range<usize>> {
+        self.next_back_range()
+    }
+
+    fn parse_range(&self, range: Range<usize>) -> Result<T, ParseError> {
+        let slice = &self.string[range];
+        __private::add_err_multi_context(T::from_str_in(slice, self.input), self.string, slice)
+    }
+
+    fn range_size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
-impl<'a, T: Parse<'a>> DoubleEndedIterator for ParseChars<'a, T> {
-    fn next_back(&mut self) -> Option<Result<T, ParseError>> {
-        self.chars.next_back().map(|(start, c)| {
-            let slice = self.string.get(start..(start + c.len_utf8())).unwrap();
-            __private::add_err_multi_context(T::from_str(slice), self.string, slice)
-        })
+impl<'a, T: Parse<'a>> ExactSizeIterator for ParseChunks<'a, T> {}
+
+impl<'a, T: Parse<'a>> ExactSizeIterator for WithOffsets<ParseChunks<'a, T>> {}
+
+/// An iterator that yields the byte range of each item alongside the parsed item.
+///
+/// The range is relative to the string that the inner iterator was created from.
+/// It is created using the `with_offsets` method on [`ParseIter`], [`ParseChars`] and [`ParseChunks`].
+///
+/// ```
+/// # use prse::{parse, ParseIter};
+/// let iter: ParseIter<u32> = parse!("10,x,30", "{:,:0}");
+/// let mut iter = iter.with_offsets();
+/// assert_eq!(iter.next(), Some((0..2, Ok(10))));
+/// let (range, result) = iter.next().unwrap();
+/// assert_eq!(range, 3..4);
+/// assert!(result.is_err());
+/// assert_eq!(iter.next(), Some((5..7, Ok(30))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct WithOffsets<I> {
+    iter: I,
+}
+
+impl<I> WithOffsets<I> {
+    /// Returns the inner iterator.
+    pub fn into_inner(self) -> I {
+        self.iter
     }
 }
 
-impl<'a, T: Parse<'a>> FusedIterator for ParseChars<'a, T> {}
+macro_rules! impl_parse_iterator {
+    ($($iter:ident),*) => {$(
+        impl<'a, T: Parse<'a>> $iter<'a, T> {
+            /// Returns an iterator that also yields the byte range of each item,
+            /// see [`WithOffsets`] for more details.
+            pub fn with_offsets(self) -> WithOffsets<Self> {
+                WithOffsets { iter: self }
+            }
+
+            /// Parses every remaining item, returning all the successfully parsed items
+            /// and all the errors instead of stopping at the first error.
+            pub fn collect_errors<B, E>(self) -> (B, E)
+            where
+                B: Default + Extend<T>,
+                E: Default + Extend<ParseError>,
+            {
+                let mut items = B::default();
+                let mut errors = E::default();
+                for item in self {
+                    match item {
+                        Ok(item) => items.extend(Some(item)),
+                        Err(e) => errors.extend(Some(e)),
+                    }
+                }
+                (items, errors)
+            }
+
+            /// Parses every remaining item, returning every error if any of the items failed to parse.
+            ///
+            /// This is only available if the alloc feature has been enabled.
+            #[cfg(feature = "alloc")]
+            pub fn try_collect<B: FromIterator<T>>(self) -> Result<B, Vec<ParseError>> {
+                let mut errors = Vec::new();
+                let items = self
+                    .filter_map(|item| item.map_err(|e| errors.push(e)).ok())
+                    .collect();
+                if errors.is_empty() {
+                    Ok(items)
+                } else {
+                    Err(errors)
+                }
+            }
+        }
+
+        impl<'a, T: Parse<'a>> Iterator for $iter<'a, T> {
+            type Item = Result<T, ParseError>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                let range = self.next_range()?;
+                Some(self.parse_range(range))
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.range_size_hint()
+            }
+
+            fn last(mut self) -> Option<Self::Item> {
+                let range = self.last_range()?;
+                Some(self.parse_range(range))
+            }
+        }
+
+        impl<'a, T: Parse<'a>> DoubleEndedIterator for $iter<'a, T> {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                let range = self.next_back_range()?;
+                Some(self.parse_range(range))
+            }
+        }
+
+        impl<'a, T: Parse<'a>> FusedIterator for $iter<'a, T> {}
+
+        impl<'a, T: Parse<'a>> $iter<'a, T> {
+            #[doc(hidden)]
+            /// Not part of public api, sets the original input that the string is a part of
+            /// so that spanned items record their position within it.
+            pub fn in_input(mut self, input: &'a str) -> Self {
+                self.input = input;
+                self
+            }
+        }
+
+        impl<'a, T: Parse<'a>> Iterator for WithOffsets<$iter<'a, T>> {
+            type Item = (Range<usize>, Result<T, ParseError>);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                let range = self.iter.next_range()?;
+                Some((range.clone(), self.iter.parse_range(range)))
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.iter.range_size_hint()
+            }
+        }
+
+        impl<'a, T: Parse<'a>> DoubleEndedIterator for WithOffsets<$iter<'a, T>> {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                let range = self.iter.next_back_range()?;
+                Some((range.clone(), self.iter.parse_range(range)))
+            }
+        }
+
+        impl<'a, T: Parse<'a>> FusedIterator for WithOffsets<$iter<'a, T>> {}
+    )*};
+}
+
+impl_parse_iterator!(ParseIter, ParseChars, ParseChunks);
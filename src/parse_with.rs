@@ -0,0 +1,70 @@
+use crate::ParseError;
+
+/// Parse a string into the implemented type using some external state `C`,
+/// such as an interner or a registry of units.
+///
+/// The types implementing [`Parse`](crate::Parse) in this crate, and the types deriving it without a context,
+/// also implement `ParseWith` for any context by ignoring it, while [`Spanned`](crate::Spanned) and
+/// [`Flags`](crate::Flags) pass the context on to their items.
+/// A type implementing `Parse` by hand needs to implement `ParseWith` the same way to be parsed with a context,
+/// while a type deriving `Parse` without a context cannot implement `ParseWith` by hand,
+/// as it would overlap with the derived implementation.
+/// The context is passed to each capture of [`parse_with!`](crate::parse_with) and [`try_parse_with!`](crate::try_parse_with),
+/// and it can be threaded through a derived type using `#[prse(context = C)]`.
+///
+/// ```
+/// # use prse::{parse_with, Parse, ParseError, ParseWith};
+/// # #[cfg(feature = "alloc")] {
+/// #[derive(Debug, PartialEq)]
+/// struct Symbol(usize);
+///
+/// #[derive(Default)]
+/// struct Interner(Vec<String>);
+///
+/// impl<'a> ParseWith<'a, Interner> for Symbol {
+///     fn from_str_with(s: &'a str, ctx: &mut Interner) -> Result<Self, ParseError> {
+///         let s = s.trim();
+///         let idx = ctx.0.iter().position(|x| x == s).unwrap_or_else(|| {
+///             ctx.0.push(s.to_string());
+///             ctx.0.len() - 1
+///         });
+///         Ok(Symbol(idx))
+///     }
+/// }
+///
+/// #[derive(Parse, Debug, PartialEq)]
+/// #[prse = "{name} = {values:,:}"]
+/// #[prse(context = Interner)]
+/// struct Assignment {
+///     name: Symbol,
+///     values: Vec<Symbol>,
+/// }
+///
+/// let mut interner = Interner::default();
+/// let (assignment, count): (Assignment, u32) = parse_with!("[x = y, x] 2", &mut interner, "[{}] {}");
+/// assert_eq!(assignment, Assignment { name: Symbol(0), values: vec![Symbol(1), Symbol(0)] });
+/// assert_eq!(count, 2);
+/// assert_eq!(interner.0, ["x", "y"]);
+/// # }
+/// ```
+pub trait ParseWith<'a, C: ?Sized> {
+    /// Parses a string `s` to a return value of this type using the context `ctx`.
+    ///
+    /// If parsing succeeds, return the value inside [`Ok`], otherwise
+    /// when the string is ill-formatted return a [`ParseError`].
+    fn from_str_with(s: &'a str, ctx: &mut C) -> Result<Self, ParseError>
+    where
+        Self: Sized;
+
+    /// Parses a string `s` which is a part of the original `input` using the context `ctx`.
+    ///
+    /// Like [`Parse::from_str_in`](crate::Parse::from_str_in), a hand-written implementation
+    /// which contains a [`Spanned`](crate::Spanned) value should forward `input` to it.
+    fn from_str_with_in(s: &'a str, input: &'a str, ctx: &mut C) -> Result<Self, ParseError>
+    where
+        Self: Sized,
+    {
+        let _ = input;
+        Self::from_str_with(s, ctx)
+    }
+}
@@ -0,0 +1,55 @@
+/// A description of the input accepted by a type deriving [`Parse`](crate::Parse).
+///
+/// The derive macro generates it as the associated `PRSE_SCHEMA` constant of the type,
+/// along with `PRSE_PATTERNS` which contains the pattern of each variant in the order they are tried.
+/// This can be used by tooling such as help screens or autocompletion.
+///
+/// ```
+/// # use prse::Parse;
+/// #[derive(Parse)]
+/// enum Command {
+///     #[prse = "move {x} {y}"]
+///     Move { x: i32, y: i32 },
+///     #[prse = "quit"]
+///     Quit,
+/// }
+///
+/// assert_eq!(Command::PRSE_PATTERNS, ["move {x} {y}", "quit"]);
+///
+/// let schema = Command::PRSE_SCHEMA;
+/// assert_eq!(schema.name, "Command");
+/// assert_eq!(schema.variants[0].prefix, "move ");
+/// assert_eq!(schema.variants[0].fields[1].name, "y");
+/// assert_eq!(schema.variants[0].fields[1].ty, "i32");
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Schema {
+    /// The name of the type.
+    pub name: &'static str,
+    /// Each pattern of the type in the order they are tried.
+    pub variants: &'static [SchemaVariant],
+    /// Whether the type can also be parsed from the discriminant of its variants.
+    pub discriminant: bool,
+}
+
+/// A single pattern of a type deriving [`Parse`](crate::Parse).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct SchemaVariant {
+    /// The name of the enum variant, or of the struct itself.
+    pub name: &'static str,
+    /// The pattern using the same syntax as the [`parse!`](crate::parse) macro.
+    pub pattern: &'static str,
+    /// The literal text that the pattern starts with, which is empty if it starts with a capture.
+    pub prefix: &'static str,
+    /// The fields parsed by the pattern.
+    pub fields: &'static [SchemaField],
+}
+
+/// A field parsed by a pattern.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct SchemaField {
+    /// The name of the field used in the pattern, or its index for tuple fields.
+    pub name: &'static str,
+    /// The type of the field as it was written.
+    pub ty: &'static str,
+}
@@ -0,0 +1,102 @@
+use crate::{Parse, ParseError, ParseWith};
+use core::fmt;
+use core::ops::{Deref, DerefMut, Range};
+
+/// A value along with the range of bytes of the input that it was parsed from.
+///
+/// The range is within the original input given to [`parse!`](crate::parse), [`try_parse!`](crate::try_parse)
+/// or [`Parse::from_str`], even when the value is part of a derived type or of a repetition.
+/// When it is part of a type implementing [`Parse`] by hand, that implementation must forward
+/// the original input through [`Parse::from_str_in`], otherwise the span is relative to the string given to that type.
+///
+/// ```
+/// # use prse::{parse, Parse, Spanned};
+/// let (name, age): (Spanned<&str>, Spanned<u8>) = parse!("name: Alice, age: 30", "name: {}, age: {}");
+/// assert_eq!(name.span, 6..11);
+/// assert_eq!(*age, 30);
+/// assert_eq!(age.span, 18..20);
+///
+/// # #[cfg(feature = "alloc")] {
+/// #[derive(Parse)]
+/// #[prse = "{key}={values:,:}"]
+/// struct Entry<'a> {
+///     key: Spanned<&'a str>,
+///     values: Vec<Spanned<u32>>,
+/// }
+///
+/// let (_, entry): (&str, Entry) = parse!("set a=1,20", "{} {}");
+/// assert_eq!(entry.key.span, 4..5);
+/// assert_eq!(entry.values[1].span, 8..10);
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct Spanned<T> {
+    /// The parsed value.
+    pub value: T,
+    /// The range of bytes of the input that the value was parsed from.
+    pub span: Range<usize>,
+}
+
+impl<T> Spanned<T> {
+    /// Creates a value with the given span.
+    pub fn new(value: T, span: Range<usize>) -> Self {
+        Spanned { value, span }
+    }
+
+    /// Returns the value without its span.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Spanned<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<'a, T: Parse<'a>> Parse<'a> for Spanned<T> {
+    fn from_str(s: &'a str) -> Result<Self, ParseError> {
+        Self::from_str_in(s, s)
+    }
+
+    fn from_str_in(s: &'a str, input: &'a str) -> Result<Self, ParseError> {
+        let value = T::from_str_in(s, input)?;
+        Ok(Spanned::new(value, span_in(s, input)))
+    }
+}
+
+impl<'a, C: ?Sized, T: ParseWith<'a, C>> ParseWith<'a, C> for Spanned<T> {
+    fn from_str_with(s: &'a str, ctx: &mut C) -> Result<Self, ParseError> {
+        Self::from_str_with_in(s, s, ctx)
+    }
+
+    fn from_str_with_in(s: &'a str, input: &'a str, ctx: &mut C) -> Result<Self, ParseError> {
+        let value = T::from_str_with_in(s, input, ctx)?;
+        Ok(Spanned::new(value, span_in(s, input)))
+    }
+}
+
+/// Returns the range of bytes of `s` within `input`.
+fn span_in(s: &str, input: &str) -> Range<usize> {
+    // A string that is not a part of the input is given a span relative to itself.
+    let start = (s.as_ptr() as usize)
+        .checked_sub(input.as_ptr() as usize)
+        .filter(|start| start + s.len() <= input.len())
+        .unwrap_or(0);
+    start..start + s.len()
+}
+
+impl<T: fmt::Display> fmt::Display for Spanned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
@@ -1,6 +1,8 @@
 mod common {
-    use prse::{parse, Parse};
-    use prse::{ParseChars, ParseIter};
+    use prse::{
+        parse, parse_with, try_parse, try_parse_with, Flags, Parse, ParseError, ParseWith, Spanned,
+    };
+    use prse::{ParseChars, ParseChunks, ParseIter, Separator};
 
     #[test]
     fn empty_literal() {
@@ -55,6 +57,961 @@ mod common {
         );
     }
 
+    fn parse_hex(s: &str) -> Result<u32, core::num::ParseIntError> {
+        u32::from_str_radix(s, 16)
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse = "{type} #{colour} {name}"]
+    struct Attributes<'a> {
+        #[prse(rename = "type")]
+        kind: &'a str,
+        #[prse(with = parse_hex)]
+        colour: u32,
+        name: &'a str,
+        #[prse(default)]
+        alpha: u8,
+        #[prse(default = u8::MAX)]
+        size: u8,
+        #[prse(skip)]
+        computed: bool,
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse = "{0}:{1}"]
+    struct TupleAttributes(
+        #[prse(skip)] bool,
+        #[prse(with = parse_hex)] u32,
+        u8,
+        #[prse(default = 7)] u8,
+    );
+
+    #[test]
+    fn parse_field_attributes() {
+        let name = "box";
+        assert_eq!(
+            Attributes {
+                kind: "square",
+                colour: 0xff8000,
+                name,
+                alpha: 0,
+                size: 255,
+                computed: false,
+            },
+            parse!("square #ff8000 box", "{}")
+        );
+        let err: Result<Attributes, _> = try_parse!("square #orange box", "{}");
+        assert!(err.is_err());
+        assert_eq!(TupleAttributes(false, 0xa, 12, 7), parse!("a:12", "{}"));
+        assert_eq!(TupleDefault(0, 5), parse!("5", "{}"));
+        assert_eq!(TupleDefault(3, 5), parse!("5 3", "{}"));
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse = "{1} {0}"]
+    #[prse = "{1}"]
+    struct TupleDefault(#[prse(default)] u8, u32);
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse = "{name}: {age}"]
+    #[prse = "{age} {name}"]
+    #[prse = "name={name};age={age}"]
+    struct Record<'a> {
+        name: &'a str,
+        #[prse(default = 18)]
+        age: u8,
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse = "yes"]
+    #[prse = "y"]
+    struct Yes;
+
+    #[test]
+    fn parse_struct_alternatives() {
+        let expected = Record {
+            name: "Ada",
+            age: 36,
+        };
+        assert_eq!(expected, parse!("Ada: 36", "{}"));
+        assert_eq!(expected, parse!("36 Ada", "{}"));
+        assert_eq!(expected, parse!("name=Ada;age=36", "{}"));
+        let res: Result<Record, _> = try_parse!("Ada", "{}");
+        assert!(res.is_err());
+        assert_eq!(Yes, parse!("y", "{}"));
+        assert_eq!(Yes, parse!("yes", "{}"));
+        let res: Result<Yes, _> = try_parse!("no", "{}");
+        assert!(res.is_err());
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse = "CMD {id}: {}!"]
+    enum Command<'a> {
+        #[prse = "move {x} {y}"]
+        Move { id: u32, x: i32, y: i32 },
+        #[prse = "say {text}"]
+        #[prse = "shout {text}"]
+        Say { id: u32, text: &'a str },
+        #[prse = "stop"]
+        Stop { id: u32 },
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse = "<{}>"]
+    enum Tag {
+        #[prse = "{}"]
+        Num(u8),
+        #[prse = "br"]
+        Break,
+    }
+
+    #[test]
+    fn parse_enum_pattern() {
+        assert_eq!(
+            Command::Move { id: 1, x: -3, y: 4 },
+            parse!("CMD 1: move -3 4!", "{}")
+        );
+        assert_eq!(
+            Command::Say { id: 2, text: "hi" },
+            parse!("CMD 2: shout hi!", "{}")
+        );
+        assert_eq!(Command::Stop { id: 3 }, parse!("CMD 3: stop!", "{}"));
+        let res: Result<Command, _> = try_parse!("move 1 2", "{}");
+        assert!(res.is_err());
+        assert_eq!(Tag::Num(7), parse!("<7>", "{}"));
+        assert_eq!(Tag::Break, parse!("<br>", "{}"));
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(rename_all = "snake_case")]
+    enum Direction {
+        North,
+        NorthEast,
+        #[prse(rename = "down")]
+        South,
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(rename_all = "UPPERCASE", sep = ":")]
+    enum Shape {
+        Circle(u32),
+        Rect {
+            w: u32,
+            #[prse(rename = "height")]
+            h: u32,
+            #[prse(skip)]
+            filled: bool,
+        },
+        #[prse = "{}x"]
+        Scale(u8),
+    }
+
+    #[test]
+    fn parse_rename_all() {
+        assert_eq!(Direction::North, parse!("north", "{}"));
+        assert_eq!(Direction::NorthEast, parse!("north_east", "{}"));
+        assert_eq!(Direction::South, parse!("down", "{}"));
+        let res: Result<Direction, _> = try_parse!("South", "{}");
+        assert!(res.is_err());
+        assert_eq!(Shape::Circle(3), parse!("CIRCLE:3", "{}"));
+        assert_eq!(
+            Shape::Rect {
+                w: 1,
+                h: 2,
+                filled: false
+            },
+            parse!("RECT:1:2", "{}")
+        );
+        assert_eq!(Shape::Scale(4), parse!("4x", "{}"));
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    enum Status<'a> {
+        #[prse(other)]
+        Unknown(&'a str),
+        #[prse = "ok"]
+        Ok,
+        #[prse = "err {}"]
+        Err(u8),
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(rename_all = "lowercase")]
+    enum Reply<'a> {
+        Ping,
+        #[prse(other)]
+        #[prse = "{code} {text}"]
+        Raw {
+            code: u16,
+            text: &'a str,
+        },
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    enum Switch {
+        #[prse = "on"]
+        On,
+        #[prse(other)]
+        Off,
+    }
+
+    #[test]
+    fn parse_other_variant() {
+        assert_eq!(Status::Ok, parse!("ok", "{}"));
+        assert_eq!(Status::Err(4), parse!("err 4", "{}"));
+        assert_eq!(Status::Unknown("err x"), parse!("err x", "{}"));
+        assert_eq!(Status::Unknown(""), parse!("", "{}"));
+        assert_eq!(Reply::Ping, parse!("ping", "{}"));
+        assert_eq!(
+            Reply::Raw {
+                code: 404,
+                text: "not found"
+            },
+            parse!("404 not found", "{}")
+        );
+        let res: Result<Reply, _> = try_parse!("pong", "{}");
+        assert!(res.is_err());
+        assert_eq!(Switch::On, parse!("on", "{}"));
+        assert_eq!(Switch::Off, parse!("anything", "{}"));
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(longest_match)]
+    enum Instruction<'a> {
+        #[prse = "move {}"]
+        Move(u32),
+        #[prse = "{}"]
+        Say(&'a str),
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(longest_match)]
+    enum Step<'a> {
+        #[prse = "go {}"]
+        Go(u32),
+        #[prse = "please go {}"]
+        Please(u32),
+        #[prse = "go"]
+        Start,
+        #[prse = "stop now"]
+        Stop,
+        #[prse(other)]
+        Unknown(&'a str),
+    }
+
+    #[test]
+    fn parse_longest_match() {
+        // Both patterns match all of the input, so the first one declared is chosen.
+        assert_eq!(Instruction::Move(3), parse!("move 3", "{}"));
+        // The longer literal only matches the end of the input.
+        assert_eq!(Instruction::Say("xx move 3"), parse!("xx move 3", "{}"));
+        assert_eq!(Instruction::Say("move up"), parse!("move up", "{}"));
+
+        assert_eq!(Step::Please(3), parse!("please go 3", "{}"));
+        assert_eq!(Step::Go(3), parse!("so go 3", "{}"));
+        assert_eq!(Step::Start, parse!("go", "{}"));
+        assert_eq!(Step::Stop, parse!("stop now", "{}"));
+        assert_eq!(Step::Unknown("stop"), parse!("stop", "{}"));
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    enum Op {
+        #[prse = "add {}"]
+        Add(i32),
+        #[prse = "sub {}"]
+        Sub(i32),
+        #[prse = "go {}"]
+        Go(u8),
+        #[prse = "go to {}"]
+        GoTo(u8),
+        #[prse = "{} items"]
+        Items(u8),
+        #[prse = "reset"]
+        Reset,
+        #[prse = "go"]
+        Start,
+    }
+
+    #[test]
+    fn parse_dispatched_variants() {
+        assert_eq!(Op::Add(3), parse!("add 3", "{}"));
+        assert_eq!(Op::Sub(-1), parse!("sub -1", "{}"));
+        assert_eq!(Op::Go(2), parse!("go 2", "{}"));
+        assert_eq!(Op::GoTo(2), parse!("go to 2", "{}"));
+        assert_eq!(Op::Items(7), parse!("7 items", "{}"));
+        assert_eq!(Op::Reset, parse!("reset", "{}"));
+        assert_eq!(Op::Start, parse!("go", "{}"));
+        assert_eq!(Op::Go(5), parse!("go 5", "{}"));
+        // Text before a leading literal is ignored as it is for a single pattern.
+        assert_eq!(Op::Add(3), parse!("now add 3", "{}"));
+        let res: Result<Op, _> = try_parse!("mul 3", "{}");
+        assert!(res.is_err());
+        let res: Result<Op, _> = try_parse!("", "{}");
+        assert!(res.is_err());
+    }
+
+    fn not_zero(day: &u8) -> Result<(), ()> {
+        if *day == 0 {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse = "{year}-{month}-{day}"]
+    #[prse(validate = Date::check_leap_day)]
+    struct Date {
+        year: u32,
+        #[prse(range = 1..=12)]
+        month: u8,
+        #[prse(validate = not_zero, range = ..=31)]
+        day: u8,
+    }
+
+    impl Date {
+        fn check_leap_day(&self) -> Result<(), ()> {
+            if self.month == 2 && self.day == 29 && self.year % 4 != 0 {
+                Err(())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    enum Interval {
+        #[prse = "{}-{}"]
+        #[prse(validate = Interval::is_ordered)]
+        Ordered(u32, u32),
+        #[prse = "{}-{}"]
+        Reversed(u32, u32),
+    }
+
+    impl Interval {
+        fn is_ordered(&self) -> Result<(), ()> {
+            match self {
+                Interval::Ordered(start, end) if start > end => Err(()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_validation() {
+        let date = Date {
+            year: 2024,
+            month: 2,
+            day: 29,
+        };
+        assert_eq!(date, parse!("2024-2-29", "{}"));
+        for invalid in [
+            "2024-13-1",
+            "2024-0-1",
+            "2024-2-0",
+            "2024-2-32",
+            "2023-2-29",
+        ] {
+            let res = Date::from_str(invalid);
+            assert!(matches!(res, Err(ParseError::Validation { .. })));
+        }
+        assert_eq!(Interval::Ordered(1, 2), parse!("1-2", "{}"));
+        assert_eq!(Interval::Reversed(3, 2), parse!("3-2", "{}"));
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(kv)]
+    struct Event<'a> {
+        level: &'a str,
+        #[prse(rename = "msg")]
+        message: &'a str,
+        code: Option<u16>,
+        #[prse(default = 1)]
+        count: u32,
+        #[prse(skip)]
+        seen: bool,
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(kv, sep = "\n", assign = ": ", deny_unknown_fields)]
+    struct Headers<'a> {
+        #[prse(rename = "Host")]
+        host: &'a str,
+        #[prse(rename = "Content-Length", range = ..1024)]
+        length: usize,
+    }
+
+    #[test]
+    fn parse_key_value() {
+        let event = Event {
+            level: "info",
+            message: "\"hello world\"",
+            code: None,
+            count: 1,
+            seen: false,
+        };
+        assert_eq!(event, parse!("msg=\"hello world\" level=info", "{}"));
+        let event = Event {
+            level: "warn",
+            message: "x",
+            code: Some(404),
+            count: 3,
+            seen: false,
+        };
+        assert_eq!(
+            event,
+            parse!("count=3  level=warn user=me msg=x code=404", "{}")
+        );
+        for invalid in ["level=info", "level=info msg=x msg=y", "level=info msg"] {
+            let res: Result<Event, _> = try_parse!(invalid, "{}");
+            assert!(res.is_err());
+        }
+
+        let headers = Headers {
+            host: "example.com",
+            length: 12,
+        };
+        assert_eq!(
+            headers,
+            parse!("Content-Length: 12\nHost: example.com\n", "{}")
+        );
+        for invalid in [
+            "Host: example.com\nContent-Length: 12\nAccept: */*",
+            "Host: example.com\nContent-Length: 2048",
+        ] {
+            let res: Result<Headers, _> = try_parse!(invalid, "{}");
+            assert!(res.is_err());
+        }
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(tokens)]
+    struct Process<'a> {
+        pid: u32,
+        user: &'a str,
+        #[prse(skip)]
+        cached: bool,
+        #[prse(range = ..=100)]
+        cpu: u8,
+        #[prse(default)]
+        command: &'a str,
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(tokens = ",")]
+    struct Row<'a>(&'a str, i32, #[prse(with = str::parse)] u8);
+
+    #[test]
+    fn parse_tokens() {
+        let process = Process {
+            pid: 42,
+            user: "root",
+            cached: false,
+            cpu: 7,
+            command: "init",
+        };
+        assert_eq!(process, parse!("  42 root\t 7   init\n", "{}"));
+        let process = Process {
+            pid: 1,
+            user: "me",
+            cached: false,
+            cpu: 0,
+            command: "",
+        };
+        assert_eq!(process, parse!("1 me 0", "{}"));
+        for invalid in ["1 me", "1 me 0 sh extra", "1 me 200 sh"] {
+            let res: Result<Process, _> = try_parse!(invalid, "{}");
+            assert!(res.is_err());
+        }
+
+        assert_eq!(Row("", -1, 4), parse!(",-1,4", "{}"));
+        let res: Result<Row, _> = try_parse!("a, 1, 2", "{}");
+        assert!(res.is_err());
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(from_str)]
+    #[prse = "{width}x{height}"]
+    struct Size {
+        width: u32,
+        height: u32,
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(from_str, rename_all = "lowercase")]
+    enum Tile<T> {
+        #[prse = "{}"]
+        Square(T),
+        Empty,
+    }
+
+    #[test]
+    fn parse_from_str() {
+        assert_eq!(
+            "3x4".parse(),
+            Ok(Size {
+                width: 3,
+                height: 4
+            })
+        );
+        assert_eq!(
+            Size::try_from("5x1"),
+            Ok(Size {
+                width: 5,
+                height: 1
+            })
+        );
+        assert!("3x".parse::<Size>().is_err());
+        assert_eq!("empty".parse(), Ok(Tile::<u8>::Empty));
+        assert_eq!(Tile::try_from("7"), Ok(Tile::Square(7_u8)));
+    }
+
+    mod renamed {
+        pub use prse as parsing;
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct NotParse;
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(crate = "self::renamed::parsing", bound = "")]
+    #[prse = "#{id}"]
+    struct Tagged<T> {
+        id: u32,
+        #[prse(default)]
+        marker: core::marker::PhantomData<T>,
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(bound = "T: Parse<'__prse_a> + Copy")]
+    #[prse = "{0:,:2}"]
+    struct Pair<T: Copy>([T; 2]);
+
+    #[test]
+    fn parse_bound_and_crate_path() {
+        let tagged: Tagged<NotParse> = parse!("#5", "{}");
+        assert_eq!(tagged.id, 5);
+        assert_eq!(Pair([1, 2]), parse!("1,2", "{}"));
+
+        let id: u32 = parse!("id: 3", "id: {}", crate = "self::renamed::parsing");
+        assert_eq!(id, 3);
+        let res: Result<u32, _> = try_parse!("id: x", "id: {}", crate = "renamed::parsing");
+        assert!(res.is_err());
+    }
+
+    #[derive(Parse, Debug, Clone, Copy, Eq, PartialEq)]
+    #[prse(discriminant)]
+    enum Level {
+        Debug = 10,
+        Info = 20,
+        Warn,
+        Error = 40,
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse(discriminant, rename_all = "lowercase")]
+    enum Permission {
+        Read = 4,
+        Write = 2,
+        Execute = 1,
+    }
+
+    #[derive(Debug, Default, Eq, PartialEq)]
+    struct Mode(u8);
+
+    impl<'a> Parse<'a> for Mode {
+        fn from_str(s: &'a str) -> Result<Self, ParseError> {
+            match s {
+                "r" => Ok(Mode(4)),
+                "w" => Ok(Mode(2)),
+                "x" => Ok(Mode(1)),
+                _ => u8::from_str(s).map(Mode),
+            }
+        }
+    }
+
+    impl IntoIterator for Mode {
+        type Item = Mode;
+        type IntoIter = core::option::IntoIter<Mode>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            Some(self).into_iter()
+        }
+    }
+
+    impl FromIterator<Mode> for Mode {
+        fn from_iter<I: IntoIterator<Item = Mode>>(iter: I) -> Self {
+            Mode(iter.into_iter().fold(0, |acc, m| acc | m.0))
+        }
+    }
+
+    #[test]
+    fn parse_discriminants() {
+        let levels: [Level; 4] = parse!("10 20 21 40", "{: :4}");
+        assert_eq!(
+            levels,
+            [Level::Debug, Level::Info, Level::Warn, Level::Error]
+        );
+        assert!(Level::from_str("30").is_err());
+        assert!(Level::from_str("info").is_err());
+
+        assert_eq!(Permission::from_str("write"), Ok(Permission::Write));
+        assert_eq!(Permission::from_str("1"), Ok(Permission::Execute));
+        assert!(Permission::from_str("3").is_err());
+
+        let Flags(mode): Flags<Mode> = parse!("mode = r | x | 8", "mode = {}");
+        assert_eq!(mode, Mode(13));
+        let Flags(mode): Flags<Mode, ','> = parse!("", "{}");
+        assert_eq!(mode, Mode(0));
+        assert!(Flags::<Mode>::from_str("r|y").is_err());
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    #[prse(tag = "{kind} {}")]
+    enum Reading {
+        #[prse(tag_value = "temp")]
+        Temperature(f32),
+        #[prse(tag_value = "gps")]
+        #[prse = "{lat},{lon}"]
+        #[prse = "({lat}, {lon})"]
+        Position { lat: f32, lon: f32 },
+        #[prse(tag_value = "off")]
+        Off,
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    #[prse(tag = "<{name}>{}", rename_all = "lowercase", sep = ",")]
+    enum Message<'a> {
+        Text(&'a str),
+        Move(i32, i32),
+        #[prse(rename = "bye")]
+        Quit,
+    }
+
+    #[test]
+    fn parse_tagged() {
+        let readings: [Reading; 4] =
+            parse!("temp 21.5; gps 51.5,-0.1; gps (1, 2); off ", "{:; :4}");
+        assert_eq!(
+            readings,
+            [
+                Reading::Temperature(21.5),
+                Reading::Position {
+                    lat: 51.5,
+                    lon: -0.1
+                },
+                Reading::Position { lat: 1.0, lon: 2.0 },
+                Reading::Off
+            ]
+        );
+        assert!(Reading::from_str("hum 40").is_err());
+        assert!(Reading::from_str("temp hot").is_err());
+        assert!(Reading::from_str("off now").is_err());
+        assert_eq!(Reading::from_str("off"), Ok(Reading::Off));
+        assert!(Reading::from_str("temp").is_err());
+
+        assert_eq!(Message::from_str("<text>hi"), Ok(Message::Text("hi")));
+        assert_eq!(Message::from_str("<move>-1,2"), Ok(Message::Move(-1, 2)));
+        assert_eq!(Message::from_str("<bye>"), Ok(Message::Quit));
+        assert!(Message::from_str("<quit>").is_err());
+    }
+
+    #[derive(Parse, Debug)]
+    #[prse = "{x},{y}"]
+    struct SpannedPoint {
+        x: Spanned<i32>,
+        y: Spanned<i32>,
+    }
+
+    #[derive(Parse, Debug)]
+    #[prse = "{name}: {point}"]
+    struct Marker<'a> {
+        name: Spanned<&'a str>,
+        point: Spanned<SpannedPoint>,
+    }
+
+    #[derive(Parse, Debug)]
+    #[prse(tag = "{kind} {}")]
+    enum SpannedReading {
+        #[prse(tag_value = "temp")]
+        Temperature(Spanned<f32>),
+    }
+
+    /// A hand-written implementation which passes the original input on to its spanned value.
+    #[derive(Debug)]
+    struct Quoted(Spanned<u8>);
+
+    impl<'a> Parse<'a> for Quoted {
+        fn from_str(s: &'a str) -> Result<Self, ParseError> {
+            Self::from_str_in(s, s)
+        }
+
+        fn from_str_in(s: &'a str, input: &'a str) -> Result<Self, ParseError> {
+            Spanned::from_str_in(s.trim_matches('"'), input).map(Quoted)
+        }
+    }
+
+    #[derive(Debug)]
+    struct UnforwardedQuoted(Spanned<u8>);
+
+    impl<'a> Parse<'a> for UnforwardedQuoted {
+        fn from_str(s: &'a str) -> Result<Self, ParseError> {
+            Spanned::from_str(s.trim_matches('"')).map(UnforwardedQuoted)
+        }
+    }
+
+    #[test]
+    fn parse_spanned() {
+        let (a, b): (Spanned<u32>, Spanned<&str>) = parse!("12 + abc", "{} + {}");
+        assert_eq!((*a, a.span), (12, 0..2));
+        assert_eq!((b.value, b.span), ("abc", 5..8));
+
+        let marker: Marker = parse!("-> home: 3,-4", "-> {}");
+        assert_eq!(marker.name.span, 3..7);
+        assert_eq!(marker.point.span, 9..13);
+        assert_eq!((*marker.point.x, marker.point.x.span.clone()), (3, 9..10));
+        assert_eq!((*marker.point.y, marker.point.y.span.clone()), (-4, 11..13));
+
+        let mut iter: ParseIter<Spanned<u8>> = parse!("[1, 22, 3]", "[{:, :0}]");
+        assert_eq!(iter.nth(1).unwrap().unwrap().span, 4..6);
+        let chars: [Spanned<char>; 2] = parse!("ab", "{::2}");
+        assert_eq!(chars[1].span, 1..2);
+        let (id, rest): (Spanned<u16>, Spanned<&str>) = parse!("0042rest", "{:w4}{}");
+        assert_eq!((id.span, rest.span), (0..4, 4..8));
+
+        let SpannedReading::Temperature(t) = SpannedReading::from_str("temp 21.5").unwrap();
+        assert_eq!(t.span, 5..9);
+
+        let (q, u): (Quoted, UnforwardedQuoted) = parse!("a = \"42\", \"7\"", "a = {}, {}");
+        assert_eq!((*q.0, q.0.span), (42, 5..7));
+        assert_eq!((*u.0, u.0.span), (7, 0..1));
+
+        let standalone = Spanned::<u8>::from_str("7").unwrap();
+        assert_eq!(standalone, Spanned::new(7, 0..1));
+        assert_eq!(standalone.into_inner(), 7);
+    }
+
+    struct Units {
+        millimetres: u32,
+        count: u32,
+    }
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct Length(u32);
+
+    impl<'a> ParseWith<'a, Units> for Length {
+        fn from_str_with(s: &'a str, ctx: &mut Units) -> Result<Self, ParseError> {
+            ctx.count += 1;
+            Ok(Length(<u32 as Parse>::from_str(s)? * ctx.millimetres))
+        }
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    #[prse = "{width}x{height}"]
+    #[prse(context = Units)]
+    struct Dimensions {
+        width: Length,
+        height: Length,
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    #[prse(context = Units)]
+    enum Figure<T> {
+        #[prse = "square {}"]
+        Square(T),
+        #[prse = "rect {}"]
+        Rect(Dimensions),
+        #[prse = "triangle {:,:3}"]
+        Triangle([T; 3]),
+        #[prse = "line {:w2}{:w2}"]
+        Line(T, u8),
+        #[prse = "empty"]
+        Empty,
+    }
+
+    #[test]
+    fn parse_with_context() {
+        let mut units = Units {
+            millimetres: 10,
+            count: 0,
+        };
+        let (size, n): (Dimensions, u8) = parse_with!("3x4 5", &mut units, "{} {}");
+        assert_eq!(size, Dimensions::from_str_with("3x4", &mut units).unwrap());
+        assert_eq!((size.width, size.height, n), (Length(30), Length(40), 5));
+
+        let lengths: [Length; 2] = parse_with!("[1, 2]", &mut units, "[{:, :2}]");
+        assert_eq!(lengths, [Length(10), Length(20)]);
+        let (a, b): (Length, Length) = parse_with!("0102", &mut units, "{:w2}{:w2}");
+        assert_eq!((a, b), (Length(10), Length(20)));
+        let (n, length): (u8, Spanned<Length>) = parse_with!("1, 2", &mut units, "{}, {}");
+        assert_eq!((n, length), (1, Spanned::new(Length(20), 3..4)));
+        let size: Result<Dimensions, _> = try_parse_with!("3xa", &mut units, "{}");
+        assert!(size.is_err());
+        assert_eq!(units.count, 11);
+
+        units.millimetres = 1000;
+        assert_eq!(
+            Figure::from_str_with("triangle 1,2,3", &mut units),
+            Ok(Figure::Triangle([Length(1000), Length(2000), Length(3000)]))
+        );
+        assert_eq!(
+            Figure::<Length>::from_str_with("rect 1x2", &mut units),
+            Ok(Figure::Rect(Dimensions {
+                width: Length(1000),
+                height: Length(2000)
+            }))
+        );
+        assert_eq!(
+            Figure::from_str_with("line 0407", &mut units),
+            Ok(Figure::Line(Length(4000), 7))
+        );
+        assert_eq!(
+            Figure::<u32>::from_str_with("square 5", &mut units),
+            Ok(Figure::Square(5))
+        );
+        assert_eq!(
+            Figure::<Length>::from_str_with("empty", &mut units),
+            Ok(Figure::Empty)
+        );
+        assert_eq!(units.count, 17);
+
+        // Each variant is only tried once, even when none of them match.
+        assert!(Figure::<Length>::from_str_with("square x", &mut units).is_err());
+        assert_eq!(units.count, 18);
+    }
+
+    #[test]
+    fn parse_schema() {
+        use prse::{SchemaField, SchemaVariant};
+
+        assert_eq!(
+            Command::PRSE_PATTERNS,
+            [
+                "CMD {id}: move {x} {y}!",
+                "CMD {id}: say {text}!",
+                "CMD {id}: shout {text}!",
+                "CMD {id}: stop!"
+            ]
+        );
+        assert_eq!(Record::PRSE_PATTERNS.len(), 3);
+        assert_eq!(Alphabets::PRSE_PATTERNS, ["{:-:2}"]);
+        assert_eq!(TupleAttributes::PRSE_PATTERNS, ["{0}:{1}"]);
+        assert_eq!(
+            Shape::PRSE_PATTERNS,
+            ["CIRCLE:{}", "RECT:{w}:{height}", "{}x"]
+        );
+        assert_eq!(
+            Headers::PRSE_PATTERNS,
+            ["Host: {Host}\nContent-Length: {Content-Length}"]
+        );
+        assert_eq!(Row::PRSE_PATTERNS, ["{0},{1},{2}"]);
+
+        let schema = Attributes::PRSE_SCHEMA;
+        assert_eq!(schema.name, "Attributes");
+        assert_eq!(
+            schema.variants,
+            [SchemaVariant {
+                name: "Attributes",
+                pattern: "{type} #{colour} {name}",
+                prefix: "",
+                fields: &[
+                    SchemaField {
+                        name: "type",
+                        ty: "&'a str"
+                    },
+                    SchemaField {
+                        name: "colour",
+                        ty: "u32"
+                    },
+                    SchemaField {
+                        name: "name",
+                        ty: "&'a str"
+                    },
+                    SchemaField {
+                        name: "alpha",
+                        ty: "u8"
+                    },
+                    SchemaField {
+                        name: "size",
+                        ty: "u8"
+                    },
+                ],
+            }]
+        );
+
+        let schema = Tag::PRSE_SCHEMA;
+        assert_eq!(schema.variants[0].name, "Num");
+        assert_eq!(schema.variants[1].prefix, "<br>");
+        assert_eq!(
+            Lifetimes::PRSE_SCHEMA.variants[0].fields[1].ty,
+            "[&'b str; 2]"
+        );
+
+        assert_eq!(
+            Reading::PRSE_PATTERNS,
+            ["temp {}", "gps {lat},{lon}", "gps ({lat}, {lon})", "off"]
+        );
+        assert_eq!(Message::PRSE_SCHEMA.variants[1].prefix, "<move>");
+
+        let schema = Level::PRSE_SCHEMA;
+        assert!(schema.discriminant);
+        assert!(schema.variants.is_empty());
+    }
+
+    #[derive(Default, Debug, Eq, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Default, Debug, Eq, PartialEq)]
+    struct Bounds {
+        min: Point,
+        max: Point,
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    #[prse = "{pos.x},{pos.y} {name}"]
+    struct Player<'a> {
+        pos: Point,
+        name: &'a str,
+    }
+
+    #[derive(Parse, Debug, Eq, PartialEq)]
+    enum Area {
+        #[prse = "{bounds.min.x}..{bounds.max.x}"]
+        Line {
+            #[prse(default = Bounds { min: Point { x: 0, y: -1 }, max: Point { x: 0, y: 1 } })]
+            bounds: Bounds,
+        },
+        #[prse = "{r#box.min.x},{r#box.min.y} {r#box.max.x},{r#box.max.y}"]
+        Rect { r#box: Bounds },
+    }
+
+    #[test]
+    fn parse_nested_fields() {
+        let player = Player {
+            pos: Point { x: 3, y: -4 },
+            name: "ferris",
+        };
+        assert_eq!(player, parse!("3,-4 ferris", "{}"));
+
+        let line = Area::Line {
+            bounds: Bounds {
+                min: Point { x: 1, y: -1 },
+                max: Point { x: 5, y: 1 },
+            },
+        };
+        assert_eq!(line, parse!("1..5", "{}"));
+        let rect = Area::Rect {
+            r#box: Bounds {
+                min: Point { x: 1, y: 2 },
+                max: Point { x: 3, y: 4 },
+            },
+        };
+        assert_eq!(rect, parse!("1,2 3,4", "{}"));
+    }
+
     #[test]
     fn parse_trim() {
         assert_eq!(
@@ -91,6 +1048,192 @@ mod common {
         assert_eq!(MultiSep { arr: [23, 1] }, parse!(":::23::::1", "{}"))
     }
 
+    #[derive(Parse, Eq, PartialEq, Debug)]
+    #[prse = "{a:[,;]:2} | {b:[ or |, ]:!3} | {c:\\s:2}"]
+    struct SeparatorSets {
+        a: [u32; 2],
+        b: [u32; 3],
+        c: [u32; 2],
+    }
+
+    #[test]
+    fn parse_separator_sets() {
+        let arr: [u8; 4] = parse!("1,2;3,4", "{:[,;]:4}");
+        assert_eq!([1, 2, 3, 4], arr);
+        let arr: [&str; 3] = parse!("a|b;c", "{:[\\||;]:3}");
+        assert_eq!(["a", "b", "c"], arr);
+        let arr: [&str; 3] = parse!("a😇bĺĺc", "{:[😇ĺ]:!3}");
+        assert_eq!(["a", "b", "c"], arr);
+        let arr: [u8; 3] = parse!("\t1   2\n3\n", "{:\\s:3}");
+        assert_eq!([1, 2, 3], arr);
+        assert_eq!(
+            SeparatorSets {
+                a: [1, 2],
+                b: [3, 4, 5],
+                c: [6, 7]
+            },
+            parse!("1;2 | 3 or 4, 5 | 6  7", "{}")
+        );
+        let mut iter: ParseIter<u8> = parse!("1 and 2, 3", "{:[, | and ]:0}");
+        assert_eq!(Some(Ok(1)), iter.next());
+        assert_eq!(Some(Ok(2)), iter.next());
+        assert_eq!(Some(Ok(3)), iter.next());
+        assert_eq!(None, iter.next());
+    }
+
+    #[test]
+    fn parse_nested_separators() {
+        let arr: [&str; 4] = parse!(r#"(1,2),[3,{4,5}],"6,\",7","8,""9""""#, "{:,:#4}");
+        assert_eq!(["(1,2)", "[3,{4,5}]", r#""6,\",7""#, r#""8,""9""""#], arr);
+        let arr: [&str; 2] = parse!("[(a b) c]  d", "{:\\s:#2}");
+        assert_eq!(["[(a b) c]", "d"], arr);
+        let (arr, rest): ([&str; 2], &str) = parse!("{[a}],b} c}", "{{{:,:#2}}} {}");
+        assert_eq!((["[a}]", "b"], "c}"), (arr, rest));
+        let mut iter: ParseIter<&str> = parse!("(a,b),,c", "{:,:#!0}");
+        assert_eq!(Some(Ok("(a,b)")), iter.next());
+        assert_eq!(Some(Ok("c")), iter.next());
+        assert_eq!(None, iter.next());
+    }
+
+    #[derive(Parse, Eq, PartialEq, Debug)]
+    #[prse = "{kind:w1}{id:b4}{name:w6t}{tags:,:2}|{flag}"]
+    struct FixedRecord<'a> {
+        kind: char,
+        id: u32,
+        name: &'a str,
+        tags: [&'a str; 2],
+        flag: bool,
+    }
+
+    #[test]
+    fn parse_fixed_width() {
+        let (a, b, c): (&str, &str, &str) = parse!("ábcdef", "{:w2}{}{:b2}");
+        assert_eq!(("áb", "cd", "ef"), (a, b, c));
+        let (a, b, c, d): (u8, &str, char, char) = parse!("12-abc😇", "{:w2}-{}{:w1}{:b4}");
+        assert_eq!((12, "ab", 'c', '😇'), (a, b, c, d));
+        assert_eq!(
+            FixedRecord {
+                kind: 'A',
+                id: 42,
+                name: "Tümmy",
+                tags: ["x", "y"],
+                flag: true,
+            },
+            parse!("A0042Tümmy x,y|true", "{}")
+        );
+        let padded: &str = parse!("ab  |", "{:w4}|");
+        assert_eq!(padded, "ab  ");
+        let trimmed: &str = parse!("ab  |", "{:w4t}|");
+        assert_eq!(trimmed, "ab");
+        let err: Result<(&str, &str), _> = try_parse!("abc", "{:w4}{}");
+        assert_eq!(
+            err,
+            Err(ParseError::Width {
+                expected: 4,
+                found: 3
+            })
+        );
+        let err: Result<(&str, &str), _> = try_parse!("ábc", "{:b1}{}");
+        assert!(err.is_err());
+        let err: Result<(&str, &str), _> = try_parse!("a-bc", "{}-{:w3}");
+        assert!(err.is_err());
+    }
+
+    #[derive(Parse, Eq, PartialEq, Debug)]
+    #[prse = "{:~2:3}"]
+    struct Codes<'a>([&'a str; 3]);
+
+    #[test]
+    fn parse_chunks() {
+        assert_eq!(Codes(["ab", "cd", "😇f"]), parse!("abcd😇f", "{}"));
+        let mut iter: ParseChunks<u16> = parse!("[123456]", "[{:~3:0}]");
+        assert_eq!(2, iter.len());
+        assert_eq!(Some(Ok(123)), iter.next());
+        assert_eq!(1, iter.len());
+        assert_eq!(Some(Ok(456)), iter.next_back());
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+        let err: Result<[u8; 2], _> = try_parse!("1234", "{:~3:2}");
+        assert_eq!(
+            err,
+            Err(ParseError::Width {
+                expected: 3,
+                found: 1
+            })
+        );
+        let empty: ParseChunks<u8> = parse!("", "{:~3:0}");
+        assert_eq!(0, empty.count());
+    }
+
+    fn assert_both_ends<'a>(iter: ParseIter<'a, &'a str>, expected: &[&str]) {
+        assert!(iter.clone().eq(expected.iter().map(|s| Ok(*s))));
+        assert!(iter.rev().eq(expected.iter().rev().map(|s| Ok(*s))));
+    }
+
+    #[test]
+    fn parse_iter_double_ended() {
+        use Separator::*;
+        assert_both_ends(ParseIter::new("a,,b,", Str(",")), &["a", "", "b"]);
+        assert_both_ends(ParseIter::new(",a,,b", Str(",")).skip_empty(), &["a", "b"]);
+        assert_both_ends(ParseIter::new("", Str(",")), &[]);
+        assert_both_ends(
+            ParseIter::new("a;b,c", AnyOf(&[",", ";"])),
+            &["a", "b", "c"],
+        );
+        assert_both_ends(
+            ParseIter::new("a or b, c", AnyOf(&[", ", " or "])),
+            &["a", "b", "c"],
+        );
+        assert_both_ends(
+            ParseIter::new("a; b|c and d#;e", AnyOf(&["; ", "|", " and ", "#", ";"])),
+            &["a", "b", "c", "d", "", "e"],
+        );
+        assert_both_ends(ParseIter::new(" a \t b\n", Whitespace), &["a", "b"]);
+        assert_both_ends(
+            ParseIter::new("(a,b),\"c,d\",e", Str(",")).nested(),
+            &["(a,b)", "\"c,d\"", "e"],
+        );
+
+        let mut iter: ParseIter<&str> = ParseIter::new("a-b-c-d", Str("-"));
+        assert_eq!(Some(Ok("d")), iter.next_back());
+        assert_eq!(Some(Ok("a")), iter.next());
+        assert_eq!("b-c", iter.remainder());
+        assert_eq!(Some(Ok("c")), iter.next_back());
+        assert_eq!(Some(Ok("b")), iter.next());
+        assert_eq!("", iter.remainder());
+        assert_eq!(None, iter.next_back());
+        assert_eq!(None, iter.next());
+
+        // A separator that overlaps with itself is matched differently from the back.
+        let iter: ParseIter<&str> = ParseIter::new("1aaa2", Str("aa"));
+        assert_eq!(Some(Ok("a2")), iter.clone().last());
+        assert!(iter.clone().eq([Ok("1"), Ok("a2")]));
+        assert!(iter.rev().eq([Ok("2"), Ok("1a")]));
+    }
+
+    #[test]
+    fn parse_iter_offsets() {
+        let iter: ParseIter<u32> = parse!("[1, 22, x]", "[{:, :0}]");
+        let mut iter = iter.with_offsets();
+        assert_eq!(Some((0..1, Ok(1))), iter.next());
+        let (range, result) = iter.next_back().unwrap();
+        assert_eq!((7..8, true), (range, result.is_err()));
+        assert_eq!(Some((3..5, Ok(22))), iter.next());
+        assert_eq!(None, iter.next());
+
+        let mut chars: ParseChars<char> = ParseChars::new("a€b");
+        assert_eq!(Some(Ok('b')), chars.next_back());
+        assert_eq!("a€", chars.remainder());
+        let mut chars = chars.with_offsets();
+        assert_eq!(Some((1..4, Ok('€'))), chars.next_back());
+
+        let chunks: ParseChunks<&str> = ParseChunks::new("aabbcc", 2).unwrap();
+        let mut chunks = chunks.with_offsets();
+        assert_eq!(3, chunks.len());
+        assert_eq!(Some((4..6, Ok("cc"))), chunks.next_back());
+        assert_eq!("aabb", chunks.into_inner().remainder());
+    }
+
     #[derive(Parse)]
     #[prse = "({a:,:!0};{b::0})"]
     struct TwoIters<'a> {
@@ -1,6 +1,9 @@
 #[cfg(test)]
 mod tests {
-    use prse::{parse, try_parse, Parse, ParseChars, ParseError};
+    use prse::{
+        parse, parse_with, try_parse, try_parse_with, Flags, Parse, ParseChars, ParseError,
+    };
+    use prse::{ParseIter, ParseWith, Spanned};
 
     #[test]
     fn ui() {
@@ -183,6 +186,541 @@ mod tests {
         assert_eq!(Some(Ok('@')), iter.next());
     }
 
+    #[test]
+    fn parse_chunks_vec() {
+        let bytes: Vec<&str> = parse!("0aff10", "{:~2:}");
+        let bytes: Vec<u8> = bytes
+            .into_iter()
+            .map(|b| u8::from_str_radix(b, 16).unwrap())
+            .collect();
+        assert_eq!(vec![10, 255, 16], bytes);
+        let err: Result<Vec<&str>, _> = try_parse!("0aff1", "{:~2:}");
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn parse_iter_collect() {
+        let iter: ParseIter<u8> = parse!("1,x,3,300", "{:,:0}");
+        let (items, errors): (Vec<_>, Vec<_>) = iter.clone().collect_errors();
+        assert_eq!(vec![1, 3], items);
+        assert_eq!(2, errors.len());
+        let collected: Result<Vec<_>, _> = iter.try_collect();
+        assert_eq!(2, collected.unwrap_err().len());
+
+        let chars: ParseChars<u8> = parse!("123", "{::0}");
+        assert_eq!(Ok(vec![1, 2, 3]), chars.try_collect::<Vec<_>>());
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    enum Shape {
+        #[prse = "circle {radius}"]
+        Circle {
+            radius: f32,
+            #[prse(default = String::from("circle"))]
+            name: String,
+        },
+        #[prse = "{name}: {width}x{height}"]
+        #[prse = "{width}x{height}"]
+        Rect {
+            width: u32,
+            height: u32,
+            #[prse(default)]
+            name: String,
+            #[prse(skip)]
+            area: Option<u32>,
+        },
+    }
+
+    #[test]
+    fn parse_variant_field_attributes() {
+        assert_eq!(
+            Shape::Circle {
+                radius: 1.5,
+                name: "circle".into()
+            },
+            parse!("circle 1.5", "{}")
+        );
+        assert_eq!(
+            Shape::Rect {
+                width: 2,
+                height: 3,
+                name: "".into(),
+                area: None
+            },
+            parse!("2x3", "{}")
+        );
+        assert_eq!(
+            Shape::Rect {
+                width: 2,
+                height: 3,
+                name: "door".into(),
+                area: None
+            },
+            parse!("door: 2x3", "{}")
+        );
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    #[prse(rename_all = "lowercase")]
+    enum Direction {
+        North,
+        South,
+        #[prse = "{}°"]
+        Degrees(u16),
+    }
+
+    #[test]
+    fn parse_variant_errors() {
+        let err = Direction::from_str("nrth").unwrap_err();
+        let ParseError::Variants { errors, suggestion } = &err else {
+            panic!("expected a variants error but found {err:?}");
+        };
+        assert_eq!(*suggestion, Some("north"));
+        let names: Vec<_> = errors.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["North", "South", "Degrees"]);
+        assert_eq!(
+            errors[0].1,
+            ParseError::Literal {
+                expected: "north".into(),
+                found: "nrth".into()
+            }
+        );
+        assert!(err
+            .to_string()
+            .starts_with("unable to parse any variant, did you mean \"north\"?"));
+
+        let err = Direction::from_str("90").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::Variants {
+                suggestion: None,
+                ..
+            }
+        ));
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    enum Request {
+        #[prse = "GET {}"]
+        Get(u32),
+        #[prse = "PUT {}"]
+        Put(u32),
+        #[prse = "POST {}"]
+        Post(u32),
+        #[prse = "DELETE {}"]
+        Delete(u32),
+    }
+
+    #[test]
+    fn parse_dispatched_errors() {
+        assert_eq!(Request::Delete(4), Request::from_str("DELETE 4").unwrap());
+        let err = Request::from_str("PUT x").unwrap_err();
+        let ParseError::Variants { errors, .. } = err else {
+            panic!("expected a variants error but found {err:?}");
+        };
+        assert_eq!(errors.len(), 4);
+        assert_eq!(
+            errors[0],
+            (
+                "Get",
+                ParseError::Literal {
+                    expected: "GET ".into(),
+                    found: "PUT x".into()
+                }
+            )
+        );
+        assert!(matches!(errors[1], ("Put", ParseError::Context { .. })));
+    }
+
+    fn is_even(value: &u32) -> Result<(), ParseError> {
+        if value % 2 == 0 {
+            Ok(())
+        } else {
+            Err(ParseError::new("expected an even number"))
+        }
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    #[prse = "{0}x{1}"]
+    struct Grid(#[prse(range = 1..10)] u32, #[prse(validate = is_even)] u32);
+
+    #[test]
+    fn parse_validation_errors() {
+        assert_eq!(Grid(3, 4), Grid::from_str("3x4").unwrap());
+        assert_eq!(
+            Grid::from_str("10x4"),
+            Err(ParseError::Validation {
+                field: Some("0"),
+                error: Box::new(ParseError::new("expected a value in the range 1..10"))
+            })
+        );
+        let err = Grid::from_str("3x5").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "validation failed for field 1:\n\texpected an even number"
+        );
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    #[prse(kv, sep = ";", assign = ":", deny_unknown_fields)]
+    struct Config {
+        width: u32,
+        height: u32,
+    }
+
+    #[test]
+    fn parse_key_value_errors() {
+        let config = Config::from_str("height:2;width:3").unwrap();
+        assert_eq!(
+            config,
+            Config {
+                width: 3,
+                height: 2
+            }
+        );
+        let errors = [
+            ("width:3", "missing key \"height\""),
+            ("width:3;height:2;depth:1", "unknown key \"depth\""),
+            ("width:3;width:2", "duplicate key \"width\""),
+        ];
+        for (input, message) in errors {
+            assert_eq!(Config::from_str(input), Err(ParseError::new(message)));
+        }
+        assert_eq!(
+            Config::from_str("width:3;height"),
+            Err(ParseError::Literal {
+                expected: ":".into(),
+                found: "height".into()
+            })
+        );
+    }
+
+    #[derive(Parse, prse::Display, Debug, PartialEq)]
+    #[prse(discriminant)]
+    enum Access {
+        None = 0,
+        Read = 4,
+        Write = 2,
+    }
+
+    #[test]
+    fn parse_discriminant_flags() {
+        let Flags(access): Flags<Vec<Access>> = parse!("access: 0 | 4|2", "access: {}");
+        assert_eq!(access, [Access::None, Access::Read, Access::Write]);
+        assert_eq!(Flags::<_, ','>(access).to_string(), "0,4,2");
+
+        assert_eq!(
+            Access::from_str("3"),
+            Err(ParseError::new("unknown discriminant 3"))
+        );
+        assert!(Flags::<Vec<Access>>::from_str("4|x").is_err());
+
+        let Flags(access): Flags<Vec<prse::Spanned<Access>>> = parse!("rw: 4 | 2", "rw: {}");
+        assert_eq!(*access[1], Access::Write);
+        assert_eq!(access[1].span, 8..9);
+    }
+
+    #[derive(Parse, prse::Display, Debug, PartialEq)]
+    #[prse(tag = "{kind}: {}", rename_all = "lowercase")]
+    enum Sample {
+        Temp(f32),
+        #[prse = "{lat}, {lon}"]
+        Gps {
+            lat: f32,
+            lon: f32,
+        },
+        #[prse(tag_value = "status")]
+        #[prse = "{:,:}"]
+        Flags(Vec<String>),
+        Off,
+    }
+
+    #[test]
+    fn parse_tagged_errors() {
+        let samples = [
+            Sample::Temp(-3.5),
+            Sample::Gps {
+                lat: 51.5,
+                lon: -0.125,
+            },
+            Sample::Flags(vec!["ok".into(), "low".into()]),
+            Sample::Off,
+        ];
+        assert_eq!(Sample::Off.to_string(), "off");
+        for sample in samples {
+            let line = sample.to_string();
+            assert_eq!(Sample::from_str(&line), Ok(sample));
+        }
+        assert_eq!(
+            Sample::from_str("hum: 40"),
+            Err(ParseError::new("unknown kind \"hum\""))
+        );
+        assert!(matches!(
+            Sample::from_str("temp: hot"),
+            Err(ParseError::Context { error, .. }) if matches!(*error, ParseError::Float(_))
+        ));
+    }
+
+    #[derive(Default)]
+    struct Interner(Vec<String>);
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct Symbol(usize);
+
+    impl<'a> ParseWith<'a, Interner> for Symbol {
+        fn from_str_with(s: &'a str, ctx: &mut Interner) -> Result<Self, ParseError> {
+            if s.is_empty() || !s.chars().all(char::is_alphanumeric) {
+                return Err(ParseError::new("invalid symbol"));
+            }
+            let idx = ctx.0.iter().position(|x| x == s).unwrap_or_else(|| {
+                ctx.0.push(s.to_string());
+                ctx.0.len() - 1
+            });
+            Ok(Symbol(idx))
+        }
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    #[prse(kv, sep = ",", context = Interner)]
+    struct Import {
+        module: Symbol,
+        #[prse(rename = "as")]
+        alias: Option<Symbol>,
+        line: u32,
+    }
+
+    #[derive(Parse, Debug, PartialEq)]
+    #[prse(tokens, context = Interner)]
+    struct Call(Symbol, #[prse(default)] u8);
+
+    #[derive(Parse, Debug, PartialEq)]
+    #[prse(tag = "{kind}({})", context = Interner)]
+    enum Statement {
+        #[prse(tag_value = "let")]
+        #[prse = "{name} = {values:,:}"]
+        Let { name: Symbol, values: Vec<Symbol> },
+        #[prse(tag_value = "call")]
+        #[prse = "{}"]
+        Call(Call),
+        #[prse(tag_value = "import")]
+        #[prse = "{}"]
+        Import(Import),
+    }
+
+    #[test]
+    fn parse_with_interner() {
+        let mut interner = Interner::default();
+        let (a, b): (Symbol, Vec<Symbol>) = parse_with!("x: y x", &mut interner, "{}: {: :}");
+        assert_eq!((a, b), (Symbol(0), vec![Symbol(1), Symbol(0)]));
+
+        let statements: Vec<Statement> = parse_with!(
+            "let(z = y,z);call(f 3);import(module=std,as=s,line=4)",
+            &mut interner,
+            "{:;:}"
+        );
+        assert_eq!(
+            statements,
+            [
+                Statement::Let {
+                    name: Symbol(2),
+                    values: vec![Symbol(1), Symbol(2)]
+                },
+                Statement::Call(Call(Symbol(3), 3)),
+                Statement::Import(Import {
+                    module: Symbol(4),
+                    alias: Some(Symbol(5)),
+                    line: 4
+                }),
+            ]
+        );
+        assert_eq!(interner.0, ["x", "y", "z", "f", "std", "s"]);
+
+        let chars: Vec<Symbol> = parse_with!("yxq", &mut interner, "{::}");
+        assert_eq!(chars, [Symbol(1), Symbol(0), Symbol(6)]);
+
+        let res: Result<Vec<Symbol>, _> = try_parse_with!("a,b c", &mut interner, "{:,:}");
+        assert!(matches!(
+            res,
+            Err(ParseError::MultiContext { failed_string, .. }) if failed_string == "b c"
+        ));
+        let err = Statement::from_str_with("call(f-3)", &mut interner).unwrap_err();
+        assert!(err.to_string().ends_with("invalid symbol"));
+        assert_eq!(interner.0.len(), 8);
+
+        let (name, Flags(flags)): (Spanned<Symbol>, Flags<Vec<Symbol>>) =
+            parse_with!("x [q | w]", &mut interner, "{} [{}]");
+        assert_eq!(name, Spanned::new(Symbol(0), 0..1));
+        assert_eq!(flags, [Symbol(6), Symbol(8)]);
+        let res = Flags::<Vec<Symbol>>::from_str_with("q|", &mut interner);
+        assert!(matches!(
+            res,
+            Err(ParseError::MultiContext { failed_string, .. }) if failed_string.is_empty()
+        ));
+        assert_eq!(interner.0.len(), 9);
+    }
+
+    fn parse_hex(s: &str) -> Result<u32, std::num::ParseIntError> {
+        u32::from_str_radix(s, 16)
+    }
+
+    fn display_hex(value: &u32, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{value:x}")
+    }
+
+    #[derive(Parse, prse::Display, Debug, PartialEq)]
+    #[prse = "{name} #{colour}: {tags:,:} [{code:w3}]"]
+    struct Layer {
+        name: String,
+        #[prse(with = parse_hex, display_with = display_hex)]
+        colour: u32,
+        tags: Vec<String>,
+        code: u32,
+        #[prse(skip)]
+        visible: bool,
+    }
+
+    #[derive(Parse, prse::Display, Debug, PartialEq)]
+    #[prse(rename_all = "lowercase")]
+    enum Figure {
+        Empty,
+        Circle(f32),
+        #[prse = "rect {w}x{h}"]
+        #[prse = "square {w}"]
+        Rect {
+            w: u32,
+            #[prse(default)]
+            h: u32,
+        },
+    }
+
+    #[derive(Parse, prse::Display, Debug, PartialEq)]
+    #[prse(kv, sep = ";")]
+    struct Settings {
+        width: u32,
+        title: Option<String>,
+        #[prse(rename = "fullscreen")]
+        is_fullscreen: bool,
+    }
+
+    #[derive(Parse, prse::Display, Debug, PartialEq)]
+    #[prse(tokens)]
+    struct Entry<'a>(&'a str, u32, #[prse(skip)] u8, f32);
+
+    #[test]
+    fn display_round_trip() {
+        let layer = Layer {
+            name: String::from("background"),
+            colour: 0xff8000,
+            tags: vec![String::from("hidden"), String::from("locked")],
+            code: 7,
+            visible: false,
+        };
+        assert_eq!(layer.to_string(), "background #ff8000: hidden,locked [007]");
+        assert_eq!(layer, parse!(layer.to_string(), "{}"));
+
+        let figures = [
+            (Figure::Empty, "empty"),
+            (Figure::Circle(1.5), "circle 1.5"),
+            (Figure::Rect { w: 2, h: 3 }, "rect 2x3"),
+        ];
+        for (figure, expected) in figures {
+            assert_eq!(figure.to_string(), expected);
+            assert_eq!(figure, parse!(expected, "{}"));
+        }
+
+        let mut settings = Settings {
+            width: 80,
+            title: None,
+            is_fullscreen: true,
+        };
+        assert_eq!(settings.to_string(), "width=80;fullscreen=true");
+        assert_eq!(settings, parse!(settings.to_string(), "{}"));
+        settings.title = Some(String::from("prse"));
+        assert_eq!(settings.to_string(), "width=80;title=prse;fullscreen=true");
+        assert_eq!(settings, parse!(settings.to_string(), "{}"));
+    }
+
+    #[derive(Parse, prse::Display, Debug, PartialEq)]
+    #[prse = "{kind:w1}{code:w4}{name:b6t}{amount}"]
+    struct Row {
+        kind: char,
+        code: i32,
+        name: String,
+        amount: u32,
+    }
+
+    #[test]
+    fn display_fixed_width() {
+        let row = Row {
+            kind: 'A',
+            code: -7,
+            name: String::from("Tüm"),
+            amount: 5,
+        };
+        assert_eq!(row.to_string(), "A-007Tüm  5");
+        assert_eq!(row, parse!(row.to_string(), "{}"));
+
+        let row = Row {
+            name: String::from("Tümmy!"),
+            ..row
+        };
+        let mut displayed = String::new();
+        assert!(std::fmt::write(&mut displayed, format_args!("{row}")).is_err());
+        assert_eq!(prse::unparse!(("ab", 'c'), "{:w3}{:b2}"), "ab c ");
+    }
+
+    #[test]
+    fn display_tokens() {
+        let entry = Entry("total", 12, 0, -0.5);
+        let displayed = entry.to_string();
+        assert_eq!(displayed, "total 12 -0.5");
+        assert_eq!(entry, parse!(displayed, "{}"));
+    }
+
+    #[test]
+    fn unparse_values() {
+        let name = "numbers";
+        let line = prse::unparse!(([3, 7], vec![1, 2, 3]), "{name}: {0:,:2} {1:-:}");
+        assert_eq!(line, "numbers: 3,7 1-2-3");
+        let (position, numbers): ([u32; 2], Vec<u32>) = parse!(line, "numbers: {:,:2} {:-:}");
+        assert_eq!(position, [3, 7]);
+        assert_eq!(numbers, [1, 2, 3]);
+
+        assert_eq!(prse::unparse!((2, 1), "{1} / {0}"), "1 / 2");
+        assert_eq!(prse::unparse!(5, "{} {{}}"), "5 {}");
+        assert_eq!(prse::unparse!("hello {name}!"), "hello numbers!");
+        assert_eq!(prse::unparse!(-4, "{:w4}"), "-004");
+        assert_eq!(prse::unparse!(1, "{}!", crate = "::prse"), "1!");
+    }
+
+    #[derive(Parse, prse::Display, Debug, PartialEq)]
+    enum Value<'a> {
+        #[prse = "[{:,:#}]"]
+        List(Vec<Value<'a>>),
+        #[prse = "\"{}\""]
+        Str(&'a str),
+        #[prse = "{}"]
+        Int(i64),
+    }
+
+    #[test]
+    fn parse_recursive() {
+        let value: Value = parse!(r#"[1, [2, "3,4", []], "[5]"]"#, "{}");
+        assert_eq!(
+            value,
+            Value::List(vec![
+                Value::Int(1),
+                Value::List(vec![Value::Int(2), Value::Str("3,4"), Value::List(vec![])]),
+                Value::Str("[5]"),
+            ])
+        );
+        let values: Vec<Value> = parse!("[1,2] | [] | 3", "{: | :#}");
+        assert_eq!(values.len(), 3);
+        let displayed = value.to_string();
+        assert_eq!(displayed, r#"[1,[2,"3,4",[]],"[5]"]"#);
+        assert_eq!(value, parse!(displayed, "{}"));
+    }
+
     #[test]
     fn parse_weird_exprs() {
         async fn async_parsing() -> u32 {
@@ -42,13 +42,6 @@ struct G {
     x: usize,
 }
 
-#[derive(Parse)]
-#[prse = "G: {a}"]
-#[prse = "G: {a} "]
-struct H {
-    a: usize,
-}
-
 #[derive(Parse)]
 #[prse = "I"]
 enum I {
@@ -97,4 +90,302 @@ enum O {
     O,
 }
 
+#[derive(Parse)]
+#[prse = "{a}"]
+struct P {
+    #[prse(with = from_hex, with = from_hex)]
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse = "{a}"]
+struct Q {
+    #[prse(skip)]
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse = "{a:,:}"]
+struct R {
+    #[prse(with = from_hex)]
+    a: Vec<u32>,
+}
+
+#[derive(Parse)]
+#[prse = "{}"]
+struct S(#[prse(rename = "a")] u32);
+
+#[derive(Parse)]
+#[prse = "{a}"]
+struct T {
+    #[prse(flatten)]
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse = "{a}"]
+struct V {
+    a: u32,
+    #[prse(skip, default)]
+    b: u32,
+}
+
+#[derive(Parse)]
+struct U {
+    #[prse(default)]
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse = "W: {a}"]
+enum W {
+    #[prse = "{a}"]
+    A { a: u32 },
+}
+
+#[derive(Parse)]
+#[prse = "X: {}, {:,:2}"]
+enum X {
+    #[prse = "{}"]
+    A(u32),
+}
+
+#[derive(Parse)]
+#[prse = "Y: {id}{}"]
+enum Y {
+    #[prse = "{x}"]
+    A { id: u32, x: u32 },
+}
+
+#[derive(Parse)]
+#[prse = "Z: {}"]
+#[prse = "Z {}"]
+enum Z {
+    #[prse = "a"]
+    A,
+}
+
+#[derive(Parse)]
+#[prse(rename_all = "Title Case")]
+enum AA {
+    A,
+}
+
+#[derive(Parse)]
+#[prse(rename_all = "lowercase")]
+struct AB {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(rename_all = "lowercase")]
+enum AC {
+    #[prse = "a"]
+    #[prse(rename = "b")]
+    A,
+}
+
+#[derive(Parse)]
+enum AD {
+    #[prse = "a"]
+    A,
+    #[prse(other)]
+    B(u32, u32),
+}
+
+#[derive(Parse)]
+enum AE {
+    #[prse(other)]
+    A(u32),
+    #[prse(other)]
+    B(u32),
+}
+
+#[derive(Parse)]
+#[prse(validate = check)]
+enum AF {
+    #[prse = "a"]
+    A,
+}
+
+#[derive(Parse)]
+enum AG {
+    #[prse = "a"]
+    #[prse(validate = check)]
+    A,
+}
+
+#[derive(Parse)]
+#[prse(validate = check)]
+struct AH {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(kv)]
+#[prse = "{a}"]
+struct AI {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(kv)]
+struct AJ(u32);
+
+#[derive(Parse)]
+#[prse(sep = ",")]
+struct AK {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(kv)]
+struct AL {
+    a: u32,
+    #[prse(rename = "a")]
+    b: u32,
+}
+
+#[derive(Parse)]
+#[prse(tokens)]
+#[prse = "{0}"]
+struct AM(u32);
+
+#[derive(Parse)]
+#[prse(tokens, kv)]
+struct AN {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(tokens = ",")]
+struct AO;
+
+#[derive(Parse)]
+#[prse = "{a.x} {a}"]
+struct AP {
+    a: (u32, u32),
+}
+
+#[derive(Parse)]
+#[prse = "{a.x} {a.x}"]
+struct AQ {
+    #[prse(default)]
+    a: (u32, u32),
+}
+
+#[derive(Parse)]
+#[prse = "{a.x}"]
+struct AR {
+    #[prse(with = from_hex)]
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(from_str)]
+#[prse = "{a}"]
+struct AS<'a> {
+    a: &'a str,
+}
+
+#[derive(Parse)]
+#[prse(from_str)]
+struct AT {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(bound = "T")]
+#[prse = "{a}"]
+struct AU<T> {
+    a: T,
+}
+
+#[derive(Parse)]
+#[prse(crate = "not a path")]
+#[prse = "{a}"]
+struct AV {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(crate = "missing")]
+#[prse = "{a}"]
+struct AW {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(discriminant)]
+enum AX {
+    A,
+    B(u32),
+}
+
+#[derive(Parse)]
+#[prse(discriminant)]
+#[prse = "{a}"]
+struct AY {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(tag = "{kind}")]
+enum AZ {
+    #[prse(tag_value = "a")]
+    A,
+}
+
+#[derive(Parse)]
+#[prse(tag = "{kind} {}")]
+enum BA {
+    #[prse(tag_value = "a")]
+    A,
+    B(u32),
+}
+
+#[derive(Parse)]
+#[prse(tag = "{kind} {}")]
+enum BB {
+    #[prse(tag_value = "a")]
+    A,
+    #[prse(tag_value = "a")]
+    B(u32),
+}
+
+#[derive(Parse)]
+#[prse = "{a}"]
+#[prse(from_str, context = ())]
+struct BC {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse(context = ())]
+struct BD {
+    a: u32,
+}
+
+#[derive(Parse)]
+#[prse = "{}"]
+struct BE(#[prse(default)] u8, u32);
+
+#[derive(Parse)]
+#[prse = "{a:,:0}"]
+#[prse(context = ())]
+struct BF<'a> {
+    a: prse::ParseIter<'a, u32>,
+}
+
+#[derive(Parse)]
+#[prse = "{a}"]
+struct BG {
+    a: u32,
+    b: u32,
+}
+
+fn from_hex(s: &str) -> Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(s, 16)
+}
+
 fn main() {}
@@ -0,0 +1,43 @@
+use prse::{unparse, Parse};
+
+#[derive(prse::Display)]
+struct A {
+    x: u32,
+}
+
+#[derive(Parse, prse::Display)]
+#[prse = "{x}"]
+struct B {
+    #[prse(with = from_hex)]
+    x: u32,
+}
+
+#[derive(Parse, prse::Display)]
+enum C {
+    #[prse = "c {}"]
+    Count(u32),
+    #[prse(other)]
+    Unknown,
+}
+
+#[derive(Parse, prse::Display)]
+#[prse = "{x:,:}"]
+struct D {
+    #[prse(display_with = display_list)]
+    x: Vec<u32>,
+}
+
+fn from_hex(s: &str) -> Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(s, 16)
+}
+
+fn display_list(_: &Vec<u32>, _: &mut std::fmt::Formatter) -> std::fmt::Result {
+    Ok(())
+}
+
+fn main() {
+    let x = 5;
+    unparse!("{x} {}");
+    unparse!(x "{x}");
+    unparse!(x, "{x.y}");
+}
@@ -10,4 +10,13 @@ fn main() {
     parse!(l, "test: {:,:999}");
     parse!(l, "test: {:,:-1}");
     parse!(l, "test: {:,:,}");
+    parse!(l, "test: {:[]:}");
+    parse!(l, "test: {:[a||b]:}");
+    parse!(l, "test: {::#}");
+    parse!(l, "test: {:w0}");
+    parse!(l, "test: {:wx}");
+    parse!(l, "test: {}{:w2}{}");
+    parse!(l, "test: {:~0:}");
+    parse!(l, "test: {:~2:!}");
+    prse::parse_with!(l, &mut (), "test: {:,:0}");
 }
@@ -5,5 +5,7 @@ fn main() {
 
     parse!(l, "test: {foo()}");
     parse!(l, "test: {-8}");
-    parse!(l, "test: {256}")
+    parse!(l, "test: {256}");
+    parse!(l, "test: {type}");
+    parse!(l, "test: {pos.x}");
 }
@@ -0,0 +1,67 @@
+#![deny(deprecated)]
+use prse::Parse;
+
+#[derive(Parse)]
+enum A<'a> {
+    #[prse = "{}"]
+    Any(&'a str),
+    #[prse = "move {}"]
+    Move(u32),
+}
+
+#[derive(Parse)]
+enum B {
+    #[prse = "{}"]
+    #[prse = "{0}"]
+    Tuple(u32),
+    #[prse = "{x}"]
+    Pos { x: u32 },
+}
+
+#[derive(Parse)]
+enum C {
+    #[prse = "go {}"]
+    Go(String),
+    #[prse = "stop"]
+    Stop,
+    #[prse = "please go now"]
+    Now,
+    #[prse = "stop"]
+    Halt,
+}
+
+// These can all be matched.
+#[derive(Parse)]
+enum D<'a> {
+    #[prse = "{}"]
+    Number(u32),
+    #[prse = "{} steps"]
+    Steps(u32),
+    #[prse = "{}"]
+    Float(f32),
+    #[prse = "go {}"]
+    Go(&'a str),
+    #[prse = "{}"]
+    Other(&'a str),
+}
+
+// The longer pattern matches more of the input.
+#[derive(Parse)]
+#[prse(longest_match)]
+enum E<'a> {
+    #[prse = "go {}"]
+    Go(&'a str),
+    #[prse = "please go now"]
+    Now,
+}
+
+#[derive(Parse)]
+#[prse(longest_match)]
+enum F<'a> {
+    #[prse = "{}"]
+    Any(&'a str),
+    #[prse = "move {}"]
+    Move(u32),
+}
+
+fn main() {}